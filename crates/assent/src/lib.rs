@@ -122,6 +122,12 @@ where
         self.steps.push(tick_id, steps)
     }
 
+    /// Discards all steps and expects the next step for `tick_id`, e.g. the tick of
+    /// a downloaded game state.
+    pub fn reset(&mut self, tick_id: TickId) {
+        self.steps.clear(tick_id);
+    }
+
     /// Returns the next expected `TickId` for inserting new steps.
     #[must_use]
     pub const fn next_expected_tick_id(&self) -> TickId {
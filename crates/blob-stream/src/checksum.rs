@@ -0,0 +1,34 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+/// Lookup table for the reflected CRC-32 (IEEE 802.3) polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < table.len() {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xEDB8_8320
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+/// Calculates the CRC-32 (IEEE 802.3) checksum of the whole blob, that the receiver uses to
+/// verify the blob once every chunk has been received.
+#[must_use]
+pub fn crc32(octets: &[u8]) -> u32 {
+    !octets.iter().fold(!0, |crc, octet| {
+        CRC32_TABLE[((crc ^ u32::from(*octet)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use crate::settings::CongestionWindowSettings;
+use monotonic_time_rs::Millis;
+use std::time::Duration;
+
+/// An additive increase, multiplicative decrease (AIMD) congestion window, that decides how
+/// many chunks can be in flight.
+#[derive(Debug)]
+pub struct CongestionWindow {
+    settings: CongestionWindowSettings,
+    size: usize,
+    acknowledged_count: usize,
+    last_decreased_at: Option<Millis>,
+}
+
+impl CongestionWindow {
+    #[must_use]
+    pub fn new(settings: CongestionWindowSettings) -> Self {
+        Self {
+            size: settings.initial.max(settings.min).min(settings.max).max(1),
+            settings,
+            acknowledged_count: 0,
+            last_decreased_at: None,
+        }
+    }
+
+    /// The number of chunks that can be in flight.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Grows the window by one chunk for every window of acknowledged chunks.
+    pub fn on_acknowledged(&mut self, chunk_count: usize) {
+        self.acknowledged_count += chunk_count;
+        while self.acknowledged_count >= self.size && self.size < self.settings.max {
+            self.acknowledged_count -= self.size;
+            self.size += 1;
+        }
+    }
+
+    /// Halves the window. Chunks sent together usually time out together, so the window
+    /// is decreased at most once every `resend_duration`.
+    pub fn on_timeout(&mut self, now: Millis, resend_duration: Duration) {
+        let is_recently_decreased = self.last_decreased_at.is_some_and(|decreased_at| {
+            now.checked_duration_since(decreased_at)
+                .is_none_or(|duration| duration < resend_duration)
+        });
+        if is_recently_decreased {
+            return;
+        }
+
+        self.size = (self.size / 2).max(self.settings.min).max(1);
+        self.acknowledged_count = 0;
+        self.last_decreased_at = Some(now);
+    }
+}
@@ -2,6 +2,7 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+use crate::checksum::crc32;
 use crate::in_logic::Logic;
 use crate::prelude::BlobError;
 use crate::protocol::TransferId;
@@ -19,6 +20,12 @@ pub enum FrontLogicError {
     BlobError(BlobError),
     UnknownTransferId(TransferId),
     ChunkSizeCanNotBeZero,
+    /// Every chunk has been received, but the blob does not match the checksum in the start
+    /// transfer. The transfer is dropped, and the blob has to be requested again.
+    ChecksumMismatch {
+        expected: u32,
+        encountered: u32,
+    },
 }
 
 impl ErrorLevelProvider for FrontLogicError {
@@ -28,6 +35,7 @@ impl ErrorLevelProvider for FrontLogicError {
             | Self::ChunkSizeCanNotBeZero
             | Self::BlobError(_)
             | Self::UnknownTransferId(_) => ErrorLevel::Info,
+            Self::ChecksumMismatch { .. } => ErrorLevel::Warning,
         }
     }
 }
@@ -49,6 +57,7 @@ pub struct Info {
 #[derive(Debug)]
 pub struct State {
     transfer_id: TransferId,
+    checksum: u32,
     logic: Logic,
 }
 
@@ -91,7 +100,7 @@ impl FrontLogic {
     /// # Arguments
     ///
     /// * `command` - A command sent by the sender to either start a new transfer or update
-    ///               an existing one with a chunk of data.
+    ///   an existing one with a chunk of data.
     ///
     /// # Returns
     ///
@@ -105,11 +114,15 @@ impl FrontLogic {
     ///
     /// This function returns an `io::Error` in the following cases:
     /// * If a `SetChunk` command is received and the transfer state has not been initialized
-    ///   (i.e., no `StartTransfer` has been processed), it returns an `io::Error` with
-    ///   `ErrorKind::InvalidData` and a message indicating that the `transfer_id` is unknown.
+    ///   (i.e., no `StartTransfer` has been processed), or the chunk belongs to another
+    ///   transfer, it returns an `io::Error` with `ErrorKind::InvalidData` and a message
+    ///   indicating that the `transfer_id` is unknown.
     ///
     /// * Any I/O error encountered during the update of the logic will be propagated.
     ///
+    /// * If the last missing chunk is received and the blob does not match the checksum in
+    ///   the `StartTransfer`, the transfer is dropped and `ChecksumMismatch` is returned.
+    ///
     /// # Example
     ///
     /// ```
@@ -123,6 +136,7 @@ impl FrontLogic {
     ///     transfer_id: 1234,
     ///     total_octet_size: 1024,
     ///     chunk_size: 256,
+    ///     checksum: 0xCBF4_3926,
     /// });
     ///
     /// let response = logic_front.receive(&start_command);
@@ -137,7 +151,7 @@ impl FrontLogic {
                 if self
                     .state
                     .as_ref()
-                    .map_or(true, |s| s.transfer_id.0 != start_transfer_data.transfer_id)
+                    .is_none_or(|s| s.transfer_id.0 != start_transfer_data.transfer_id)
                 {
                     debug!(
                         "received a start transfer for {}. sending ack.",
@@ -150,30 +164,48 @@ impl FrontLogic {
                     // Either logic is not set or the transfer_id is different, so we start with a fresh InLogic.
                     self.state = Some(State {
                         transfer_id: TransferId(start_transfer_data.transfer_id),
+                        checksum: start_transfer_data.checksum,
                         logic: Logic::new(
                             start_transfer_data.total_octet_size as usize,
                             start_transfer_data.chunk_size,
                         ),
                     });
-                    self.should_reply_ack = true;
                 }
+                // A repeated start transfer means that the sender has not received the ack yet
+                self.should_reply_ack = true;
                 Ok(())
             }
             SenderToReceiverFrontCommands::SetChunk(chunk_data) => {
-                if let Some(ref mut state) = self.state {
-                    trace!(
-                        "received chunk {}  (transfer:{})",
-                        chunk_data.data.chunk_index,
-                        chunk_data.transfer_id.0
-                    );
-                    state.logic.receive(&chunk_data.data)?;
-                    if state.logic.is_complete() {
+                let Some(state) = self
+                    .state
+                    .as_mut()
+                    .filter(|state| state.transfer_id == chunk_data.transfer_id)
+                else {
+                    return Err(FrontLogicError::UnknownTransferId(chunk_data.transfer_id));
+                };
+                trace!(
+                    "received chunk {}  (transfer:{})",
+                    chunk_data.data.chunk_index,
+                    chunk_data.transfer_id.0
+                );
+                let was_complete = state.logic.is_complete();
+                state.logic.receive(&chunk_data.data)?;
+                if !was_complete {
+                    if let Some(blob) = state.logic.blob() {
                         trace!("received all chunks!");
+                        let encountered = crc32(blob);
+                        if encountered != state.checksum {
+                            let expected = state.checksum;
+                            debug!("blob checksum {encountered:08X} differs from {expected:08X}, dropping transfer");
+                            self.state = None;
+                            return Err(FrontLogicError::ChecksumMismatch {
+                                expected,
+                                encountered,
+                            });
+                        }
                     }
-                    Ok(())
-                } else {
-                    Err(FrontLogicError::UnknownTransferId(chunk_data.transfer_id))
                 }
+                Ok(())
             }
         }
     }
@@ -98,7 +98,10 @@ impl BlobStreamIn {
 
         let expected_size = if chunk_index == chunk_count - 1 {
             // It was the last chunk
-            if self.octet_count % self.fixed_chunk_size as usize == 0 {
+            if self
+                .octet_count
+                .is_multiple_of(self.fixed_chunk_size as usize)
+            {
                 self.fixed_chunk_size as usize
             } else {
                 self.octet_count % self.fixed_chunk_size as usize
@@ -2,6 +2,8 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+pub mod checksum;
+pub mod congestion;
 pub mod err;
 pub mod in_logic;
 pub mod in_logic_front;
@@ -12,5 +14,6 @@ pub mod out_stream;
 pub mod prelude;
 pub mod protocol;
 pub mod protocol_front;
+pub mod settings;
 
 type ChunkIndex = usize;
@@ -2,18 +2,22 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+use crate::checksum::crc32;
 use crate::out_stream::{BlobStreamOut, OutStreamError};
 use crate::prelude::{SetChunkData, SetChunkFrontData, TransferId};
+use crate::settings::{CongestionWindowSettings, ResendTiming};
 use monotonic_time_rs::Millis;
+use std::rc::Rc;
 use std::time::Duration;
 
 #[allow(unused)]
 #[derive(Debug)]
 pub struct Logic {
     out_stream: BlobStreamOut,
-    blob: Vec<u8>,
+    blob: Rc<[u8]>,
     fixed_chunk_size: u16,
     transfer_id: TransferId,
+    checksum: u32,
 }
 
 impl Logic {
@@ -24,12 +28,31 @@ impl Logic {
         fixed_chunk_size: u16,
         resend_duration: Duration,
         blob: &[u8],
+    ) -> Result<Self, OutStreamError> {
+        Self::from_shared(
+            transfer_id,
+            fixed_chunk_size,
+            ResendTiming::Fixed(resend_duration),
+            Rc::from(blob),
+        )
+    }
+
+    /// Sends a blob that can be shared with other streams, without copying it.
+    ///
+    /// # Errors
+    /// `OutStreamError::BlobIsTooLarge` if the blob has too many chunks
+    pub fn from_shared(
+        transfer_id: TransferId,
+        fixed_chunk_size: u16,
+        resend_timing: ResendTiming,
+        blob: Rc<[u8]>,
     ) -> Result<Self, OutStreamError> {
         let chunk_count = blob.len().div_ceil(fixed_chunk_size as usize);
         let chunk_count = u32::try_from(chunk_count).map_err(OutStreamError::BlobIsTooLarge)?;
         Ok(Self {
-            out_stream: BlobStreamOut::new(chunk_count, resend_duration),
-            blob: blob.to_vec(),
+            out_stream: BlobStreamOut::with_resend_timing(chunk_count, resend_timing),
+            checksum: crc32(&blob),
+            blob,
             transfer_id,
             fixed_chunk_size,
         })
@@ -91,11 +114,22 @@ impl Logic {
     /// `OutStreamError` // TODO:
     pub fn set_waiting_for_chunk_index(
         &mut self,
+        now: Millis,
         waiting_for_index: usize,
         receive_mask: u64,
     ) -> Result<(), OutStreamError> {
         self.out_stream
-            .set_waiting_for_chunk_index(waiting_for_index, receive_mask)
+            .set_waiting_for_chunk_index(now, waiting_for_index, receive_mask)
+    }
+
+    pub fn set_congestion_window(&mut self, settings: CongestionWindowSettings) {
+        self.out_stream.set_congestion_window(settings);
+    }
+
+    /// The round trip time measured from the acknowledged chunks, if any.
+    #[must_use]
+    pub const fn round_trip_time(&self) -> Option<Duration> {
+        self.out_stream.round_trip_time()
     }
 
     #[must_use]
@@ -113,6 +147,12 @@ impl Logic {
         self.fixed_chunk_size
     }
 
+    /// The CRC-32 of the whole blob.
+    #[must_use]
+    pub const fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
     #[must_use]
     pub const fn transfer_id(&self) -> TransferId {
         self.transfer_id
@@ -7,8 +7,10 @@ use crate::out_stream::OutStreamError;
 use crate::prelude::{
     ReceiverToSenderFrontCommands, SenderToReceiverFrontCommands, StartTransferData, TransferId,
 };
+use crate::settings::{BlobStreamOutSettings, ResendTiming};
 use log::{debug, trace};
 use monotonic_time_rs::Millis;
+use std::rc::Rc;
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -23,9 +25,13 @@ pub struct OutLogicFront {
     out_stream: Logic,
     phase: Phase,
     transfer_id: TransferId,
+    max_chunk_count_each_send: usize,
 }
 
 impl OutLogicFront {
+    /// Sends a copy of the blob, with the default settings except for the chunk size
+    /// and resend duration.
+    ///
     /// # Errors
     /// returns `OutStreamError` if the blob is too large
     #[allow(unused)]
@@ -35,10 +41,41 @@ impl OutLogicFront {
         resend_duration: Duration,
         blob: &[u8],
     ) -> Result<Self, OutStreamError> {
+        Self::from_shared(
+            transfer_id,
+            &BlobStreamOutSettings {
+                chunk_size: fixed_chunk_size,
+                resend_timing: ResendTiming::Fixed(resend_duration),
+                ..BlobStreamOutSettings::default()
+            },
+            Rc::from(blob),
+        )
+    }
+
+    /// Sends a blob that can be shared with other streams, e.g. the same game state
+    /// to many receivers, without copying it.
+    ///
+    /// # Errors
+    /// returns `OutStreamError` if the blob is too large
+    pub fn from_shared(
+        transfer_id: TransferId,
+        settings: &BlobStreamOutSettings,
+        blob: Rc<[u8]>,
+    ) -> Result<Self, OutStreamError> {
+        let mut out_stream = Logic::from_shared(
+            transfer_id,
+            settings.chunk_size,
+            settings.resend_timing,
+            blob,
+        )?;
+        if let Some(congestion_window) = settings.congestion_window {
+            out_stream.set_congestion_window(congestion_window);
+        }
         Ok(Self {
-            out_stream: Logic::new(transfer_id, fixed_chunk_size, resend_duration, blob)?,
+            out_stream,
             phase: Phase::StartTransfer,
             transfer_id,
+            max_chunk_count_each_send: settings.max_chunk_count_each_send,
         })
     }
 
@@ -46,6 +83,7 @@ impl OutLogicFront {
     /// can return `OutStreamError`
     pub fn receive(
         &mut self,
+        now: Millis,
         command: &ReceiverToSenderFrontCommands,
     ) -> Result<(), OutStreamError> {
         match self.phase {
@@ -64,6 +102,7 @@ impl OutLogicFront {
             Phase::Transfer => match command {
                 ReceiverToSenderFrontCommands::AckChunk(ack_chunk_front) => {
                     self.out_stream.set_waiting_for_chunk_index(
+                        now,
                         ack_chunk_front.data.waiting_for_chunk_index as usize,
                         ack_chunk_front.data.receive_mask_after_last,
                     )?;
@@ -92,15 +131,15 @@ impl OutLogicFront {
                         transfer_id: self.transfer_id.0,
                         total_octet_size: self.out_stream.octet_size(),
                         chunk_size: self.out_stream.chunk_size(),
+                        checksum: self.out_stream.checksum(),
                     },
                 )])
             }
 
             Phase::Transfer => {
-                const MAX_CHUNK_COUNT_EACH_SEND: usize = 10;
                 let set_chunks: Vec<_> = self
                     .out_stream
-                    .send(now, MAX_CHUNK_COUNT_EACH_SEND)
+                    .send(now, self.max_chunk_count_each_send)
                     .iter()
                     .map(|front_data| SenderToReceiverFrontCommands::SetChunk(front_data.clone()))
                     .collect();
@@ -128,6 +167,12 @@ impl OutLogicFront {
         self.out_stream.is_received_by_remote()
     }
 
+    /// The round trip time measured from the acknowledged chunks, if any.
+    #[must_use]
+    pub const fn round_trip_time(&self) -> Option<Duration> {
+        self.out_stream.round_trip_time()
+    }
+
     #[must_use]
     pub const fn transfer_id(&self) -> TransferId {
         self.out_stream.transfer_id()
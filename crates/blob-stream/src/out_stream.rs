@@ -2,6 +2,8 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+use crate::congestion::CongestionWindow;
+use crate::settings::{CongestionWindowSettings, ResendTiming};
 use monotonic_time_rs::Millis;
 use std::cmp::min;
 use std::num::TryFromIntError;
@@ -21,12 +23,13 @@ pub enum OutStreamError {
 /// Each `BlobStreamOutEntry` holds metadata about a chunk, including:
 /// - `timer`: The time when the chunk was last sent, or `None` if it has not been sent.
 /// - `index`: The index of the chunk.
-/// - `start` and `end`: Byte ranges representing the chunk's position within the full blob.
+/// - `send_count`: How many times the chunk has been sent.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BlobStreamOutEntry {
     pub last_sent_at: Option<Millis>,
     pub index: u32,
     pub is_received_by_remote: bool,
+    pub send_count: u32,
 }
 
 impl BlobStreamOutEntry {
@@ -45,6 +48,7 @@ impl BlobStreamOutEntry {
             last_sent_at: None,
             index,
             is_received_by_remote: false,
+            send_count: 0,
         }
     }
 
@@ -55,6 +59,51 @@ impl BlobStreamOutEntry {
     /// * `time` - The `Instant` at which the entry is being sent.
     pub fn sent_at_time(&mut self, time: Millis) {
         self.last_sent_at = Some(time);
+        self.send_count = self.send_count.saturating_add(1);
+    }
+
+    /// Returns when the chunk was sent, if it has only been sent once. The acknowledgement of
+    /// a resent chunk can not tell which of the sends it belongs to, so it is not measured.
+    const fn sent_once_at(&self) -> Option<Millis> {
+        if self.send_count == 1 {
+            self.last_sent_at
+        } else {
+            None
+        }
+    }
+}
+
+/// Estimates the round trip time from acknowledged chunks, in the same way as TCP (RFC 6298).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RoundTripEstimator {
+    smoothed: Option<Duration>,
+    variation: Duration,
+}
+
+impl RoundTripEstimator {
+    pub fn add_sample(&mut self, sample: Duration) {
+        match self.smoothed {
+            None => {
+                self.smoothed = Some(sample);
+                self.variation = sample / 2;
+            }
+            Some(smoothed) => {
+                self.variation = (self.variation * 3 + smoothed.abs_diff(sample)) / 4;
+                self.smoothed = Some((smoothed * 7 + sample) / 8);
+            }
+        }
+    }
+
+    /// The smoothed round trip time, or `None` if nothing has been measured yet.
+    #[must_use]
+    pub const fn round_trip_time(&self) -> Option<Duration> {
+        self.smoothed
+    }
+
+    /// How long to wait for an acknowledgement, or `None` if nothing has been measured yet.
+    #[must_use]
+    pub fn retransmission_timeout(&self) -> Option<Duration> {
+        self.smoothed.map(|smoothed| smoothed + self.variation * 4)
     }
 }
 
@@ -69,7 +118,9 @@ pub struct BlobStreamOut {
     pub(crate) entries: Vec<BlobStreamOutEntry>,
     start_index_to_send: usize,
     index_to_start_from_if_not_filled_up: usize,
-    resend_duration: Duration,
+    resend_timing: ResendTiming,
+    round_trip: RoundTripEstimator,
+    congestion_window: Option<CongestionWindow>,
     chunk_count_received_by_remote: usize,
 }
 
@@ -91,6 +142,16 @@ impl BlobStreamOut {
     /// This function will panic if `fixed_chunk_size` is zero.
     #[must_use]
     pub fn new(chunk_count: u32, resend_duration: Duration) -> Self {
+        Self::with_resend_timing(chunk_count, ResendTiming::Fixed(resend_duration))
+    }
+
+    /// Creates a new `BlobStreamOut` instance, that resends chunks according to `resend_timing`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `chunk_count` is zero.
+    #[must_use]
+    pub fn with_resend_timing(chunk_count: u32, resend_timing: ResendTiming) -> Self {
         assert_ne!(chunk_count, 0, "chunk_count cannot be zero");
 
         // Initialize the entries vector by chunking the blob data
@@ -99,7 +160,9 @@ impl BlobStreamOut {
 
         Self {
             entries,
-            resend_duration,
+            resend_timing,
+            round_trip: RoundTripEstimator::default(),
+            congestion_window: None,
             index_to_start_from_if_not_filled_up: 0,
             start_index_to_send: 0,
             chunk_count_received_by_remote: 0,
@@ -112,10 +175,43 @@ impl BlobStreamOut {
         self.entries.len() as u32
     }
 
+    /// The round trip time measured from the acknowledged chunks, if any.
+    #[must_use]
+    pub const fn round_trip_time(&self) -> Option<Duration> {
+        self.round_trip.round_trip_time()
+    }
+
+    /// Limits the number of chunks in flight with a congestion window, that grows while
+    /// chunks are acknowledged and shrinks when they time out.
+    pub fn set_congestion_window(&mut self, settings: CongestionWindowSettings) {
+        self.congestion_window = Some(CongestionWindow::new(settings));
+    }
+
+    /// The number of chunks that can be in flight, or `None` if there is no congestion window.
+    #[must_use]
+    pub fn congestion_window_size(&self) -> Option<usize> {
+        self.congestion_window.as_ref().map(CongestionWindow::size)
+    }
+
+    /// The minimum time that must elapse before a chunk is resent.
+    #[must_use]
+    pub fn resend_duration(&self) -> Duration {
+        match self.resend_timing {
+            ResendTiming::Fixed(resend_duration) => resend_duration,
+            ResendTiming::RoundTrip { initial, min, max } => self
+                .round_trip
+                .retransmission_timeout()
+                .unwrap_or(initial)
+                .max(min)
+                .min(max),
+        }
+    }
+
     /// Sets the starting index from which to send the next chunk.
     ///
     /// # Arguments
     ///
+    /// * `now` - The time the acknowledgement was received, used to measure the round trip time.
     /// * `index` - The starting index of the next chunk to be sent.
     ///
     /// # Errors
@@ -123,6 +219,7 @@ impl BlobStreamOut {
     #[allow(clippy::missing_panics_doc)]
     pub fn set_waiting_for_chunk_index(
         &mut self,
+        now: Millis,
         index: usize,
         receive_mask: u64,
     ) -> Result<(), OutStreamError> {
@@ -133,11 +230,15 @@ impl BlobStreamOut {
         }
         let start = index + 1;
         let end = min(self.entries.len(), start + 64);
+        let mut newest_acknowledged_sent_at = None;
+        let previous_chunk_count_received_by_remote = self.chunk_count_received_by_remote;
 
         for previously_received_entry in &mut self.entries[0..index] {
             if !previously_received_entry.is_received_by_remote {
                 previously_received_entry.is_received_by_remote = true;
                 self.chunk_count_received_by_remote += 1;
+                newest_acknowledged_sent_at =
+                    newest_acknowledged_sent_at.max(previously_received_entry.sent_once_at());
             }
         }
 
@@ -164,6 +265,8 @@ impl BlobStreamOut {
                 if !entry.is_received_by_remote {
                     entry.is_received_by_remote = true;
                     self.chunk_count_received_by_remote += 1;
+                    newest_acknowledged_sent_at =
+                        newest_acknowledged_sent_at.max(entry.sent_once_at());
                 }
             } else {
                 // it is not allowed to go from being received by remote to suddenly not be received anymore.
@@ -175,12 +278,25 @@ impl BlobStreamOut {
             mask >>= 1;
         }
 
+        if let Some(round_trip_time) =
+            newest_acknowledged_sent_at.and_then(|sent_at| now.checked_duration_since(sent_at))
+        {
+            self.round_trip.add_sample(round_trip_time);
+        }
+
+        if let Some(congestion_window) = &mut self.congestion_window {
+            congestion_window.on_acknowledged(
+                self.chunk_count_received_by_remote - previous_chunk_count_received_by_remote,
+            );
+        }
+
         Ok(())
     }
 
     /// Sends up to `max_count` chunks, starting from the configured `start_index_to_send`.
     /// Resends chunks if enough time has passed since their last send, or fills in additional
-    /// chunks if the number of filtered chunks is less than `max_count`.
+    /// chunks if the number of filtered chunks is less than `max_count`. With a congestion
+    /// window, only the chunks that fit in the window next to the chunks in flight are sent.
     ///
     /// # Arguments
     ///
@@ -193,6 +309,30 @@ impl BlobStreamOut {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn send(&mut self, now: Millis, max_count: usize) -> Vec<u32> {
+        let resend_duration = self.resend_duration();
+        let max_count = if let Some(congestion_window) = &mut self.congestion_window {
+            let mut in_flight_count = 0;
+            let mut has_timed_out = false;
+            for entry in self
+                .entries
+                .iter()
+                .filter(|entry| !entry.is_received_by_remote)
+            {
+                if let Some(sent_at) = entry.last_sent_at {
+                    if now.duration_since(sent_at) >= resend_duration {
+                        has_timed_out = true;
+                    } else {
+                        in_flight_count += 1;
+                    }
+                }
+            }
+            if has_timed_out {
+                congestion_window.on_timeout(now, resend_duration);
+            }
+            max_count.min(congestion_window.size().saturating_sub(in_flight_count))
+        } else {
+            max_count
+        };
         // Filter by index range, timer expiration, and limit the number of results
         let mut filtered_out_indices: Vec<u32> = self
             .entries
@@ -204,7 +344,7 @@ impl BlobStreamOut {
                 !entry.is_received_by_remote
                     && entry
                     .last_sent_at
-                    .map_or(true, |t| now.duration_since(t) >= self.resend_duration)
+                    .is_none_or(|t| now.duration_since(t) >= resend_duration)
             })
             .map(|entry| entry.index)
             .collect(); // Collect into a Vec
@@ -229,7 +369,7 @@ impl BlobStreamOut {
                 .filter(|entry| {
                     // Ensure that we are not duplicating any already selected entries
                     !entry.is_received_by_remote
-                        && !filtered_out_indices.iter().any(|e| *e == entry.index)
+                        && !filtered_out_indices.contains(&entry.index)
                 })
                 .map(|entry| entry.index)
                 .take(expected_remaining) // Take only the number of remaining entries
@@ -9,11 +9,12 @@
 //! imports needed in your code.
 pub use {
     crate::err::BlobError,
-    crate::in_logic_front::{FrontLogic, Info},
+    crate::in_logic_front::{FrontLogic, FrontLogicError, Info},
     crate::out_logic_front::OutLogicFront,
     crate::out_stream::OutStreamError,
     crate::protocol::{SetChunkData, StartTransferData, TransferId},
     crate::protocol_front::{
         ReceiverToSenderFrontCommands, SenderToReceiverFrontCommands, SetChunkFrontData,
     },
+    crate::settings::{BlobStreamOutSettings, CongestionWindowSettings, ResendTiming},
 };
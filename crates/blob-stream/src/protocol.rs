@@ -49,7 +49,6 @@ impl TransferId {
     ///
     /// This function will return an `io::Error` if there is an issue with writing to the stream.
     /// This could happen if the stream is closed or if there are underlying I/O errors during the write operation.
-
     pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
         stream.write_u16(self.0)
     }
@@ -99,6 +98,8 @@ pub struct StartTransferData {
     pub transfer_id: u16, // Unique transfer_id for this session
     pub total_octet_size: u32,
     pub chunk_size: u16,
+    /// The CRC-32 of the whole blob, see [`crate::checksum::crc32`].
+    pub checksum: u32,
 }
 
 impl StartTransferData {
@@ -110,7 +111,8 @@ impl StartTransferData {
     pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
         stream.write_u16(self.transfer_id)?;
         stream.write_u32(self.total_octet_size)?;
-        stream.write_u16(self.chunk_size)
+        stream.write_u16(self.chunk_size)?;
+        stream.write_u32(self.checksum)
     }
 
     /// # Errors
@@ -121,11 +123,13 @@ impl StartTransferData {
         let transfer_id = stream.read_u16()?;
         let total_octet_size = stream.read_u32()?;
         let chunk_size = stream.read_u16()?;
+        let checksum = stream.read_u32()?;
 
         Ok(Self {
             transfer_id,
             total_octet_size,
             chunk_size,
+            checksum,
         })
     }
 }
@@ -95,7 +95,6 @@ impl SenderToReceiverFrontCommands {
     ///
     /// This function will return an `io::Error` if there is an issue with writing to the stream.
     /// This could happen if the stream is closed or if there are underlying I/O errors during the write operation.
-
     pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
         stream.write_u8(self.to_octet())?;
         match self {
@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use std::time::Duration;
+
+/// Decides how long to wait for an acknowledgement before a chunk is sent again.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResendTiming {
+    /// Chunks are resent after the same duration for every receiver.
+    Fixed(Duration),
+    /// Chunks are resent after the round trip time measured from the acknowledged chunks,
+    /// with margin for its variation, clamped to `min` and `max`. `initial` is used until
+    /// the first measurement.
+    RoundTrip {
+        initial: Duration,
+        min: Duration,
+        max: Duration,
+    },
+}
+
+/// Limits for the congestion window, the number of chunks that can be sent but not yet
+/// acknowledged. The window grows by one chunk each time a whole window of chunks has been
+/// acknowledged, and is halved when chunks time out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CongestionWindowSettings {
+    pub initial: usize,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Default for CongestionWindowSettings {
+    fn default() -> Self {
+        Self {
+            initial: 10,
+            min: 2,
+            max: 128,
+        }
+    }
+}
+
+/// Settings for sending a blob.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlobStreamOutSettings {
+    /// The number of octets in each chunk, except the last one.
+    pub chunk_size: u16,
+    pub resend_timing: ResendTiming,
+    /// The maximum number of chunks to send each time the stream is asked to send.
+    pub max_chunk_count_each_send: usize,
+    /// Limits the chunks in flight, or `None` to only be limited by `max_chunk_count_each_send`.
+    pub congestion_window: Option<CongestionWindowSettings>,
+}
+
+impl Default for BlobStreamOutSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1024,
+            resend_timing: ResendTiming::Fixed(Duration::from_millis(32 * 3)),
+            max_chunk_count_each_send: 10,
+            congestion_window: Some(CongestionWindowSettings::default()),
+        }
+    }
+}
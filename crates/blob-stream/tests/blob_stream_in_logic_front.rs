@@ -2,14 +2,18 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+use nimble_blob_stream::checksum::crc32;
 use nimble_blob_stream::prelude::*;
 
+const COMPLETE_BLOB: [u8; 9] = [0xba, 0xbc, 0xbd, 0xbe, 0xff, 0x11, 0xfe, 0x22, 0x42];
+
 #[test_log::test]
 fn start_transfer() {
     let start_transfer = SenderToReceiverFrontCommands::StartTransfer(StartTransferData {
         transfer_id: 1,
         total_octet_size: 8,
         chunk_size: 2,
+        checksum: 0,
     });
 
     let mut logic = FrontLogic::new();
@@ -31,6 +35,7 @@ fn drop_previous_transfer() {
         transfer_id: 1,
         total_octet_size: 8,
         chunk_size: 2,
+        checksum: 0,
     });
 
     let mut logic = FrontLogic::new();
@@ -52,6 +57,7 @@ fn drop_previous_transfer() {
             transfer_id: 2,
             total_octet_size: 8,
             chunk_size: 2,
+            checksum: 0,
         });
 
         logic
@@ -106,6 +112,7 @@ fn complete_transfer() {
         transfer_id: TRANSFER_ID.0,
         total_octet_size: 9,
         chunk_size: 4,
+        checksum: crc32(&COMPLETE_BLOB),
     });
 
     let mut logic = FrontLogic::new();
@@ -164,7 +171,7 @@ fn complete_transfer() {
         logic
             .blob()
             .expect("blob should be ready after receiving three chunks"),
-        &[0xba, 0xbc, 0xbd, 0xbe, 0xff, 0x11, 0xfe, 0x22, 0x42]
+        &COMPLETE_BLOB
     );
 
     let info_after_complete = logic
@@ -176,3 +183,156 @@ fn complete_transfer() {
     assert_eq!(info_after_complete.chunk_count_received, 3);
     assert_eq!(info_after_complete.waiting_for_chunk_index, 3);
 }
+
+#[test_log::test]
+fn checksum_mismatch_drops_transfer() {
+    const TRANSFER_ID_VALUE: u16 = 0x3212;
+    let start_transfer = SenderToReceiverFrontCommands::StartTransfer(StartTransferData {
+        transfer_id: TRANSFER_ID_VALUE,
+        total_octet_size: 9,
+        chunk_size: 4,
+        checksum: crc32(&COMPLETE_BLOB),
+    });
+
+    let mut logic = FrontLogic::new();
+
+    logic
+        .receive(&start_transfer)
+        .expect("start transfer should work");
+    logic.send().expect("should work to send");
+
+    set_chunk_and_check(
+        &mut logic,
+        TRANSFER_ID_VALUE,
+        0,
+        &[0xba, 0xbc, 0xbd, 0xbe],
+        1,
+        0b0,
+    );
+    set_chunk_and_check(
+        &mut logic,
+        TRANSFER_ID_VALUE,
+        1,
+        &[0xff, 0x11, 0xfe, 0x22],
+        2,
+        0b0,
+    );
+
+    let corrupt_last_chunk = SenderToReceiverFrontCommands::SetChunk(SetChunkFrontData {
+        transfer_id: TransferId(TRANSFER_ID_VALUE),
+        data: SetChunkData {
+            chunk_index: 2,
+            payload: vec![0x43],
+        },
+    });
+
+    let err = logic
+        .receive(&corrupt_last_chunk)
+        .expect_err("a blob that does not match the checksum should be rejected");
+
+    assert!(matches!(
+        err,
+        FrontLogicError::ChecksumMismatch { expected, encountered }
+            if expected == crc32(&COMPLETE_BLOB) && encountered != expected
+    ));
+    assert!(logic.blob().is_none());
+    assert!(logic.info().is_none());
+}
+
+#[test_log::test]
+fn repeated_start_transfer_is_acked_again() {
+    const TRANSFER_ID_VALUE: u16 = 0x3213;
+    let start_transfer = SenderToReceiverFrontCommands::StartTransfer(StartTransferData {
+        transfer_id: TRANSFER_ID_VALUE,
+        total_octet_size: 9,
+        chunk_size: 4,
+        checksum: crc32(&COMPLETE_BLOB),
+    });
+
+    let mut logic = FrontLogic::new();
+
+    logic
+        .receive(&start_transfer)
+        .expect("start transfer should work");
+    assert_eq!(
+        logic.send(),
+        Some(ReceiverToSenderFrontCommands::AckStart(TRANSFER_ID_VALUE))
+    );
+
+    set_chunk_and_check(
+        &mut logic,
+        TRANSFER_ID_VALUE,
+        0,
+        &[0xba, 0xbc, 0xbd, 0xbe],
+        1,
+        0b0,
+    );
+
+    // The sender has not received the ack, the transfer continues where it was
+    logic
+        .receive(&start_transfer)
+        .expect("repeated start transfer should work");
+    assert_eq!(
+        logic.send(),
+        Some(ReceiverToSenderFrontCommands::AckStart(TRANSFER_ID_VALUE))
+    );
+
+    set_chunk_and_check(
+        &mut logic,
+        TRANSFER_ID_VALUE,
+        1,
+        &[0xff, 0x11, 0xfe, 0x22],
+        2,
+        0b0,
+    );
+}
+
+#[test_log::test]
+fn chunk_for_other_transfer_is_rejected() {
+    const TRANSFER_ID_VALUE: u16 = 0x3214;
+    let start_transfer = SenderToReceiverFrontCommands::StartTransfer(StartTransferData {
+        transfer_id: TRANSFER_ID_VALUE,
+        total_octet_size: 9,
+        chunk_size: 4,
+        checksum: crc32(&COMPLETE_BLOB),
+    });
+
+    let mut logic = FrontLogic::new();
+
+    let chunk_for_transfer = |transfer_id: u16| {
+        SenderToReceiverFrontCommands::SetChunk(SetChunkFrontData {
+            transfer_id: TransferId(transfer_id),
+            data: SetChunkData {
+                chunk_index: 0,
+                payload: vec![0xba, 0xbc, 0xbd, 0xbe],
+            },
+        })
+    };
+
+    let err = logic
+        .receive(&chunk_for_transfer(TRANSFER_ID_VALUE))
+        .expect_err("a chunk before the start transfer should be rejected");
+    assert!(matches!(
+        err,
+        FrontLogicError::UnknownTransferId(TransferId(TRANSFER_ID_VALUE))
+    ));
+
+    logic
+        .receive(&start_transfer)
+        .expect("start transfer should work");
+    logic.send().expect("should work to send");
+
+    let err = logic
+        .receive(&chunk_for_transfer(TRANSFER_ID_VALUE - 1))
+        .expect_err("a chunk of a previous transfer should be rejected");
+    assert!(matches!(
+        err,
+        FrontLogicError::UnknownTransferId(TransferId(transfer_id))
+            if transfer_id == TRANSFER_ID_VALUE - 1
+    ));
+
+    let info = logic
+        .info()
+        .expect("there should be info ready after a transfer being started");
+    assert_eq!(info.chunk_count_received, 0);
+}
@@ -58,6 +58,7 @@ fn blob_stream() {
             info!("ack: {:?}", ack);
             out_logic
                 .set_waiting_for_chunk_index(
+                    now,
                     ack.waiting_for_chunk_index as usize,
                     ack.receive_mask_after_last,
                 )
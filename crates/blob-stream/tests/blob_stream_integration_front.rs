@@ -6,10 +6,14 @@ use crate::helper::generate_deterministic_blob_array;
 use log::trace;
 use monotonic_time_rs::{Millis, MillisDuration};
 use nimble_blob_stream::out_logic_front::OutLogicFront;
-use nimble_blob_stream::prelude::TransferId;
+use nimble_blob_stream::prelude::{
+    BlobStreamOutSettings, CongestionWindowSettings, ReceiverToSenderFrontCommands, ResendTiming,
+    TransferId,
+};
 use rand::prelude::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
+use std::rc::Rc;
 use std::time::Duration;
 
 pub mod helper;
@@ -45,7 +49,7 @@ fn blob_stream_front() {
                 let commands_from_receiver = in_logic.send().expect("should work to send");
                 if !drop_rng.gen_bool(0.2) {
                     out_logic
-                        .receive(&commands_from_receiver)
+                        .receive(now, &commands_from_receiver)
                         .expect("should work");
                 } else {
                     trace!("dropped from receiver to sender: {:?}", send_command);
@@ -64,3 +68,105 @@ fn blob_stream_front() {
 
     assert!(out_logic.is_received_by_remote());
 }
+
+#[test_log::test]
+fn blob_stream_front_with_congestion_window() {
+    const CHUNK_SIZE: u16 = 4;
+    const CHUNK_COUNT: u32 = 300;
+    const OCTET_COUNT: usize = (CHUNK_SIZE as usize * (CHUNK_COUNT as usize - 1)) + 1;
+    const ITERATION_COUNT: usize = 200;
+
+    let seed = 87654321;
+    let blob_to_transfer = generate_deterministic_blob_array(OCTET_COUNT, seed);
+    let mut drop_rng = StdRng::seed_from_u64(seed);
+
+    let mut in_logic = nimble_blob_stream::in_logic_front::FrontLogic::new();
+    let mut out_logic = OutLogicFront::from_shared(
+        TransferId(42),
+        &BlobStreamOutSettings {
+            chunk_size: CHUNK_SIZE,
+            resend_timing: ResendTiming::Fixed(Duration::from_millis(31 * 3)),
+            max_chunk_count_each_send: 10,
+            congestion_window: Some(CongestionWindowSettings::default()),
+        },
+        Rc::from(blob_to_transfer.as_slice()),
+    )
+    .expect("should work to create logic");
+
+    let mut now = Millis::new(0);
+
+    for _ in 0..ITERATION_COUNT {
+        let send_commands = out_logic.send(now).expect("should work");
+        for send_command in send_commands {
+            // Intentionally drop commands (datagrams)
+            if drop_rng.gen_bool(0.2) {
+                trace!("dropped from sender to receiver: {:?}", send_command);
+                continue;
+            }
+            in_logic.receive(&send_command).expect("should work");
+            let commands_from_receiver = in_logic.send().expect("should work to send");
+            if drop_rng.gen_bool(0.2) {
+                trace!(
+                    "dropped from receiver to sender: {:?}",
+                    commands_from_receiver
+                );
+                continue;
+            }
+            out_logic
+                .receive(now, &commands_from_receiver)
+                .expect("should work");
+        }
+        now += MillisDuration::from_millis(32);
+    }
+
+    assert_eq!(
+        in_logic.blob().expect("blob should be ready"),
+        blob_to_transfer
+    );
+
+    assert!(out_logic.is_received_by_remote());
+}
+
+#[test_log::test]
+fn new_uses_the_default_settings_of_from_shared() {
+    const CHUNK_SIZE: u16 = 4;
+    const OCTET_COUNT: usize = CHUNK_SIZE as usize * 100;
+
+    let blob_to_transfer = generate_deterministic_blob_array(OCTET_COUNT, 1234);
+    let resend_duration = Duration::from_millis(31 * 3);
+
+    let mut out_logic = OutLogicFront::new(
+        TransferId(42),
+        CHUNK_SIZE,
+        resend_duration,
+        blob_to_transfer.as_slice(),
+    )
+    .expect("should work to create logic");
+    let mut shared_out_logic = OutLogicFront::from_shared(
+        TransferId(42),
+        &BlobStreamOutSettings {
+            chunk_size: CHUNK_SIZE,
+            resend_timing: ResendTiming::Fixed(resend_duration),
+            ..BlobStreamOutSettings::default()
+        },
+        Rc::from(blob_to_transfer.as_slice()),
+    )
+    .expect("should work to create logic");
+
+    let mut now = Millis::new(0);
+    for _ in 0..4 {
+        // Nothing is acknowledged after the start, so the congestion window limits the chunks
+        let send_commands = out_logic.send(now).expect("should work");
+        assert_eq!(
+            send_commands,
+            shared_out_logic.send(now).expect("should work")
+        );
+
+        for logic in [&mut out_logic, &mut shared_out_logic] {
+            logic
+                .receive(now, &ReceiverToSenderFrontCommands::AckStart(42))
+                .expect("should work");
+        }
+        now += MillisDuration::from_millis(32);
+    }
+}
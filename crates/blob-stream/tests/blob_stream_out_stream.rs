@@ -4,6 +4,7 @@
  */
 use monotonic_time_rs::{Millis, MillisDuration};
 use nimble_blob_stream::out_stream::BlobStreamOut;
+use nimble_blob_stream::prelude::{CongestionWindowSettings, ResendTiming};
 use std::time::Duration;
 
 #[test_log::test]
@@ -48,7 +49,7 @@ fn check_last_sent_time() {
     }
 
     stream
-        .set_waiting_for_chunk_index(3, 0)
+        .set_waiting_for_chunk_index(now, 3, 0)
         .expect("set_waiting_for_chunk_index must not fail");
 
     {
@@ -58,3 +59,74 @@ fn check_last_sent_time() {
         assert_eq!(entries[0], 3);
     }
 }
+
+#[test_log::test]
+fn resend_follows_measured_round_trip_time() {
+    let mut stream = BlobStreamOut::with_resend_timing(
+        4,
+        ResendTiming::RoundTrip {
+            initial: Duration::from_millis(200),
+            min: Duration::from_millis(20),
+            max: Duration::from_millis(1000),
+        },
+    );
+    assert_eq!(stream.resend_duration(), Duration::from_millis(200));
+
+    let mut now = Millis::new(0);
+    assert_eq!(stream.send(now, 2), [0, 1]);
+
+    now += MillisDuration::from_millis(40);
+    stream
+        .set_waiting_for_chunk_index(now, 2, 0)
+        .expect("set_waiting_for_chunk_index must not fail");
+    assert_eq!(stream.round_trip_time(), Some(Duration::from_millis(40)));
+    assert_eq!(stream.resend_duration(), Duration::from_millis(120));
+    assert_eq!(stream.send(now, 2), [2, 3]);
+
+    now += MillisDuration::from_millis(60);
+    assert!(stream.send(now, 2).is_empty());
+
+    now += MillisDuration::from_millis(60);
+    assert_eq!(stream.send(now, 2), [2, 3]);
+
+    // Resent chunks are not measured, since it is unknown which send was acknowledged
+    now += MillisDuration::from_millis(10);
+    stream
+        .set_waiting_for_chunk_index(now, 4, 0)
+        .expect("set_waiting_for_chunk_index must not fail");
+    assert_eq!(stream.round_trip_time(), Some(Duration::from_millis(40)));
+    assert!(stream.is_received_by_remote());
+}
+
+#[test_log::test]
+fn congestion_window_grows_with_acks_and_shrinks_on_timeout() {
+    let mut stream = BlobStreamOut::new(64, Duration::from_millis(100));
+    stream.set_congestion_window(CongestionWindowSettings {
+        initial: 4,
+        min: 2,
+        max: 8,
+    });
+
+    let mut now = Millis::new(0);
+    assert_eq!(stream.send(now, 10), [0, 1, 2, 3]);
+
+    // The window is full until the chunks are acknowledged
+    now += MillisDuration::from_millis(10);
+    assert!(stream.send(now, 10).is_empty());
+
+    now += MillisDuration::from_millis(10);
+    stream
+        .set_waiting_for_chunk_index(now, 4, 0)
+        .expect("set_waiting_for_chunk_index must not fail");
+    assert_eq!(stream.congestion_window_size(), Some(5));
+    assert_eq!(stream.send(now, 10), [4, 5, 6, 7, 8]);
+
+    now += MillisDuration::from_millis(110);
+    assert_eq!(stream.send(now, 10), [4, 5]);
+    assert_eq!(stream.congestion_window_size(), Some(2));
+
+    // The chunks that timed out together only shrink the window once
+    now += MillisDuration::from_millis(10);
+    assert!(stream.send(now, 10).is_empty());
+    assert_eq!(stream.congestion_window_size(), Some(2));
+}
@@ -5,8 +5,10 @@
 use err_rs::{ErrorLevel, ErrorLevelProvider};
 use nimble_blob_stream::in_logic_front::FrontLogicError;
 use nimble_blob_stream::prelude::BlobError;
+use nimble_protocol::host_to_client::{ConnectionRejectedReason, JoinGameRejectedReason};
 use nimble_protocol::ClientRequestId;
 use std::{fmt, io};
+use tick_id::TickId;
 use tick_queue::QueueError;
 
 #[derive(Debug)]
@@ -16,6 +18,10 @@ pub enum ClientLogicError {
         expected: ClientRequestId,
         encountered: ClientRequestId,
     },
+    WrongLeaveResponseRequestId {
+        expected: ClientRequestId,
+        encountered: ClientRequestId,
+    },
     WrongConnectResponseRequestId(ClientRequestId),
     WrongDownloadRequestId,
     DownloadResponseWasUnexpected,
@@ -30,6 +36,14 @@ pub enum ClientLogicError {
     TooManyAuthoritativeSteps,
     LatencyIsTooBig,
     TooManyStepsInRange,
+    ConnectionRejected(ConnectionRejectedReason),
+    JoinGameRejected(JoinGameRejectedReason),
+    /// The first authoritative steps after the game state download start after the tick
+    /// of the game state, so the steps in between are missing.
+    AuthoritativeStepGap {
+        state_tick_id: TickId,
+        first_tick_id: TickId,
+    },
 }
 
 impl From<BlobError> for ClientLogicError {
@@ -63,17 +77,21 @@ impl ErrorLevelProvider for ClientLogicError {
             | Self::QueueError(_)
             | Self::CanNotPushEmptyPredictedSteps
             | Self::TooManyAuthoritativeSteps
-            | Self::TooManyStepsInRange => ErrorLevel::Critical,
+            | Self::TooManyStepsInRange
+            | Self::ConnectionRejected(_)
+            | Self::AuthoritativeStepGap { .. } => ErrorLevel::Critical,
             Self::WrongConnectResponseRequestId(_)
             | Self::DownloadResponseWasUnexpected
             | Self::UnexpectedBlobChannelCommand
             | Self::ReceivedConnectResponseWhenNotConnecting
-            | Self::WrongJoinResponseRequestId { .. } => ErrorLevel::Info,
+            | Self::WrongJoinResponseRequestId { .. }
+            | Self::WrongLeaveResponseRequestId { .. } => ErrorLevel::Info,
             Self::WrongDownloadRequestId
             | Self::BlobError(_)
             | Self::MillisFromLowerError
             | Self::AbsoluteTimeError
-            | Self::LatencyIsTooBig => ErrorLevel::Warning,
+            | Self::LatencyIsTooBig
+            | Self::JoinGameRejected(_) => ErrorLevel::Warning,
             Self::FrontLogicErr(err) => err.error_level(),
         }
     }
@@ -108,12 +126,28 @@ impl fmt::Display for ClientLogicError {
                 f,
                 "wrong join response, expected {expected:?}, encountered: {encountered:?}"
             ),
+            Self::WrongLeaveResponseRequestId {
+                expected,
+                encountered,
+            } => write!(
+                f,
+                "wrong leave response, expected {expected:?}, encountered: {encountered:?}"
+            ),
             Self::CanNotPushEmptyPredictedSteps => write!(f, "CanNotPushEmptyPredictedSteps"),
             Self::MillisFromLowerError => write!(f, "millis from lower"),
             Self::AbsoluteTimeError => write!(f, "absolute time"),
             Self::TooManyAuthoritativeSteps => write!(f, "TooManyAuthoritativeSteps"),
             Self::LatencyIsTooBig => write!(f, "Latency Is Too Big"),
             Self::TooManyStepsInRange => write!(f, "Too ManySteps"),
+            Self::ConnectionRejected(reason) => write!(f, "connection rejected: {reason}"),
+            Self::JoinGameRejected(reason) => write!(f, "join game rejected: {reason}"),
+            Self::AuthoritativeStepGap {
+                state_tick_id,
+                first_tick_id,
+            } => write!(
+                f,
+                "authoritative steps start at {first_tick_id}, after the game state tick {state_tick_id}"
+            ),
         }
     }
 }
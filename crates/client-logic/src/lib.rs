@@ -22,11 +22,11 @@ and consistency of the game state across all participants.
 ## Features
 
 - **Connection Management**: Handles connecting to the host, agreeing on protocol versions,
-    and managing connection states.
+  and managing connection states.
 - **Game State Handling**: Downloads and maintains the complete game state from the host.
 - **Participant Management**: Adds and removes players from the game session dynamically.
 - **Step Prediction and Reconciliation**: Sends predicted player steps to the host and reconciles
-    them with authoritative steps received from the host.
+  them with authoritative steps received from the host.
 - **Blob Streaming**: Manages blob streaming for efficient game state transfers.
 
 ## Usage
@@ -47,17 +47,20 @@ use flood_rs::BufferDeserializer;
 use flood_rs::{Deserialize, Serialize};
 use log::{debug, trace};
 use metricator::{AggregateMetric, MinMaxAvg};
-use monotonic_time_rs::{Millis, MillisLow16};
-use nimble_blob_stream::prelude::{FrontLogic, SenderToReceiverFrontCommands};
+use monotonic_time_rs::{Millis, MillisDuration, MillisLow16};
+use nimble_blob_stream::prelude::{FrontLogic, FrontLogicError, SenderToReceiverFrontCommands};
 use nimble_participant::ParticipantId;
 use nimble_protocol::client_to_host::{
     ConnectRequest, DownloadGameStateRequest, JoinGameType, JoinPlayerRequest, JoinPlayerRequests,
+    LeaveGameRequest, SessionPauseRequest,
 };
 use nimble_protocol::host_to_client::{
-    ConnectionAccepted, DownloadGameStateResponse, GameStepResponseHeader, PongInfo,
+    ConnectionAccepted, ConnectionRejected, ConnectionRejectedReason, DownloadGameStateResponse,
+    GameStepResponseHeader, JoinGameRejected, JoinGameRejectedReason, PartyAndSessionSecret,
+    PongInfo,
 };
 use nimble_protocol::prelude::*;
-use nimble_protocol::{ClientRequestId, NIMBLE_PROTOCOL_VERSION};
+use nimble_protocol::{ClientRequestId, SessionId, NIMBLE_PROTOCOL_VERSION};
 use nimble_step::Step;
 use nimble_step_map::StepMap;
 use std::fmt::Debug;
@@ -78,6 +81,9 @@ pub enum ClientLogicPhase {
 
     /// Sending predicted steps from the client to the host.
     SendPredictedSteps,
+
+    /// The host rejected the connect request. Nothing is sent until [`ClientLogic::reconnect`].
+    ConnectionRejected(ConnectionRejectedReason),
 }
 
 pub type LocalIndex = u8;
@@ -104,21 +110,40 @@ pub struct ClientLogic<
 
     connect_request_id: Option<ClientRequestId>,
 
+    /// Opaque token sent in the connect request, so the host can authenticate the client.
+    client_token: Vec<u8>,
+
+    /// The game session on the host to connect to.
+    session_id: SessionId,
+
     /// Represents the player's join game request, if available.
     joining_player: Option<Vec<LocalIndex>>,
 
+    /// Represents the player's leave game request, if available.
+    leaving_player: Option<Vec<LocalIndex>>,
+
     /// Holds the current game state.
     state: Option<StateT>,
 
+    /// The tick of the current game state, that the first authoritative step is applied to.
+    state_tick_id: Option<TickId>,
+
     /// Manages the blob stream logic for the client.
     blob_stream_client: FrontLogic,
 
+    /// The request id of the latest download game state request.
+    download_state_request_id: u8,
+
     /// Stores the outgoing predicted steps from the client.
     outgoing_predicted_steps: Queue<StepMap<StepT>>,
 
     /// Stores the incoming authoritative steps from the host.
     incoming_authoritative_steps: Queue<StepMap<Step<StepT>>>,
 
+    /// The tick of the downloaded game state, until the authoritative step for that tick
+    /// has been received.
+    unverified_state_tick_id: Option<TickId>,
+
     /// Represents the current phase of the client's logic.
     phase: ClientLogicPhase,
 
@@ -129,10 +154,34 @@ pub struct ClientLogic<
     latency: AggregateMetric<u16>,
 
     /// Tracks the buffer step count on the server.
-    //server_buffer_count: AggregateMetric<u8>,
+    server_buffer_count: AggregateMetric<u8>,
+
     joining_request_id: ClientRequestId,
 
+    leaving_request_id: ClientRequestId,
+
     local_players: Vec<LocalPlayer>,
+
+    /// Spectators follow the authoritative steps without joining any participants.
+    spectator: bool,
+
+    /// The secret received when joining, used to get the same participants back after a reconnect.
+    party_and_session_secret: Option<PartyAndSessionSecret>,
+
+    /// Set when the local players should be joined again using the party secret.
+    rejoining: bool,
+
+    /// The reason the host gave for rejecting the last join game request.
+    join_rejected_reason: Option<JoinGameRejectedReason>,
+
+    /// The tick the session is paused at, according to the latest game step response.
+    session_paused_at: Option<TickId>,
+
+    /// When the host resumes the paused session, according to the latest game step response.
+    session_resume_at: Option<Millis>,
+
+    /// Set when the session should be paused or resumed, until the host reports that it is.
+    requested_session_pause: Option<bool>,
 }
 
 impl<
@@ -147,20 +196,78 @@ impl<
         Self {
             joining_player: None,
             joining_request_id: ClientRequestId(0),
+            leaving_player: None,
+            leaving_request_id: ClientRequestId(0),
             blob_stream_client: FrontLogic::new(),
+            download_state_request_id: 0x99,
             outgoing_predicted_steps: Queue::default(),
             incoming_authoritative_steps: Queue::default(),
+            unverified_state_tick_id: None,
             server_buffer_delta_tick_id: AggregateMetric::new(3).unwrap(),
-            //server_buffer_count: AggregateMetric::new(3).unwrap(),
+            server_buffer_count: AggregateMetric::new(3).unwrap(),
             state: None,
+            state_tick_id: None,
             phase: ClientLogicPhase::RequestConnect,
             local_players: Vec::new(),
             deterministic_simulation_version,
             connect_request_id: None,
+            client_token: Vec::new(),
+            session_id: SessionId::default(),
             latency: AggregateMetric::<u16>::new(10).unwrap().with_unit("ms"),
+            spectator: false,
+            party_and_session_secret: None,
+            rejoining: false,
+            join_rejected_reason: None,
+            session_paused_at: None,
+            session_resume_at: None,
+            requested_session_pause: None,
         }
     }
 
+    /// Starts over on a new connection to the host.
+    ///
+    /// The game state is downloaded again. If the local players have joined, they are
+    /// automatically joined again using the party secret, so they get the same participant ids.
+    pub fn reconnect(&mut self) {
+        self.connect_request_id = None;
+        self.phase = ClientLogicPhase::RequestConnect;
+        self.blob_stream_client = FrontLogic::new();
+        self.outgoing_predicted_steps = Queue::default();
+        self.incoming_authoritative_steps = Queue::default();
+        self.unverified_state_tick_id = None;
+        self.state = None;
+        self.state_tick_id = None;
+        self.leaving_player = None;
+
+        if self.party_and_session_secret.is_some() && !self.local_players.is_empty() {
+            let local_indices: Vec<LocalIndex> = self
+                .local_players
+                .iter()
+                .map(|local_player| local_player.index)
+                .collect();
+            self.joining_player = Some(local_indices);
+            self.rejoining = true;
+        }
+    }
+
+    /// Returns the secret that was received when the local players joined, if any.
+    pub const fn party_and_session_secret(&self) -> Option<&PartyAndSessionSecret> {
+        self.party_and_session_secret.as_ref()
+    }
+
+    /// Sets if this client is a spectator. A spectator downloads the game state and follows
+    /// the authoritative steps, but never sends join game requests or predicted steps.
+    ///
+    /// # Arguments
+    /// * `spectator`: `true` if the client should only follow the game.
+    pub fn set_spectator(&mut self, spectator: bool) {
+        self.spectator = spectator;
+    }
+
+    pub const fn is_spectator(&self) -> bool {
+        self.spectator
+    }
+
     /// Returns a reference to the incoming authoritative steps.
     pub const fn debug_authoritative_steps(&self) -> &Queue<StepMap<Step<StepT>>> {
         &self.incoming_authoritative_steps
@@ -184,12 +291,58 @@ impl<
         }
     }
 
+    /// Sets the opaque token that is sent in the connect request, e.g. a ticket issued by a backend.
+    ///
+    /// # Arguments
+    /// * `client_token`: The token that the host uses to authenticate the client.
+    pub fn set_client_token(&mut self, client_token: &[u8]) {
+        self.client_token = client_token.to_vec();
+    }
+
+    /// Sets the game session on the host that the connect request asks for.
+    ///
+    /// # Arguments
+    /// * `session_id`: The identifier of the session to connect to.
+    pub fn set_session_id(&mut self, session_id: SessionId) {
+        self.session_id = session_id;
+    }
+
     /// Sets the joining player request for this client.
     ///
     /// # Arguments
     /// * `join_game_request`: The join game request to send to the host.
     pub fn set_joining_player(&mut self, local_players: &[LocalIndex]) {
         self.joining_player = Some(local_players.to_vec());
+        self.join_rejected_reason = None;
+    }
+
+    /// Sets the leaving player request for this client.
+    ///
+    /// # Arguments
+    /// * `local_players`: The local indices of the players that should leave the game.
+    pub fn set_leaving_player(&mut self, local_players: &[LocalIndex]) {
+        self.leaving_player = Some(local_players.to_vec());
+    }
+
+    /// Asks the host to pause or resume the session. The request is sent until the host
+    /// reports that the session is paused or that a resume is scheduled. The host must allow clients to pause,
+    /// and the client must have joined players.
+    ///
+    /// # Arguments
+    /// * `paused`: `true` to pause the session, `false` to resume it.
+    pub fn request_session_pause(&mut self, paused: bool) {
+        self.requested_session_pause = Some(paused);
+    }
+
+    /// Returns the tick that the session is paused at, or `None` if the session is running.
+    pub const fn session_paused_at(&self) -> Option<TickId> {
+        self.session_paused_at
+    }
+
+    /// Returns the time when the host resumes the paused session, or `None` if no resume
+    /// is scheduled.
+    pub const fn session_resume_at(&self) -> Option<Millis> {
+        self.session_resume_at
     }
 
     /// Generates a download state request command to send to the host.
@@ -230,12 +383,15 @@ impl<
                 patch: self.deterministic_simulation_version.patch(),
             },
             client_request_id: ClientRequestId(0),
+            session_id: self.session_id,
+            client_token: self.client_token.clone(),
         };
 
         ClientToHostCommands::ConnectType(connect_request)
     }
 
-    /// Sends the predicted steps to the host.
+    /// Sends the predicted steps to the host. Spectators have no predicted steps, so for them
+    /// the request only acknowledges the received authoritative steps.
     ///
     /// # Returns
     /// A `ClientToHostCommands` representing the predicted steps.
@@ -268,12 +424,18 @@ impl<
     pub fn send(&mut self, now: Millis) -> Vec<ClientToHostCommands<StepT>> {
         let mut commands: Vec<ClientToHostCommands<StepT>> = vec![];
 
+        if let ClientLogicPhase::ConnectionRejected(_) = self.phase {
+            return commands;
+        }
+
         if self.phase != ClientLogicPhase::RequestConnect {
             // Always send ping when connected
             let ping = ClientToHostCommands::Ping(now.to_lower());
             commands.push(ping);
 
-            if let Some(joining_players) = &self.joining_player {
+            // Spectators never join any participants
+            let joining_players = self.joining_player.as_ref().filter(|_| !self.spectator);
+            if let Some(joining_players) = joining_players {
                 debug!("connected. send join_game_request {:?}", joining_players);
 
                 let player_requests = joining_players
@@ -282,9 +444,15 @@ impl<
                         local_index: *local_index,
                     })
                     .collect();
+                let join_game_type = match self.party_and_session_secret {
+                    Some(secret) if self.rejoining => {
+                        JoinGameType::UseSessionSecret(secret.session_secret)
+                    }
+                    _ => JoinGameType::NoSecret,
+                };
                 let join_command = ClientToHostCommands::JoinGameType(JoinGameRequest {
                     client_request_id: self.joining_request_id,
-                    join_game_type: JoinGameType::NoSecret,
+                    join_game_type,
                     player_requests: JoinPlayerRequests {
                         players: player_requests,
                     },
@@ -292,6 +460,23 @@ impl<
                 trace!("send join command: {join_command:?}");
                 commands.push(join_command);
             }
+
+            if let Some(leaving_players) = &self.leaving_player {
+                debug!("connected. send leave_game_request {:?}", leaving_players);
+
+                let leave_command = ClientToHostCommands::LeaveGameType(LeaveGameRequest {
+                    client_request_id: self.leaving_request_id,
+                    local_indices: leaving_players.clone(),
+                });
+                trace!("send leave command: {leave_command:?}");
+                commands.push(leave_command);
+            }
+
+            if let Some(paused) = self.requested_session_pause {
+                commands.push(ClientToHostCommands::SessionPause(SessionPauseRequest {
+                    paused,
+                }));
+            }
         }
 
         let normal_commands: Vec<ClientToHostCommands<StepT>> = match self.phase {
@@ -305,6 +490,7 @@ impl<
                 })
             }
             ClientLogicPhase::RequestConnect => [self.send_connect_request()].to_vec(),
+            ClientLogicPhase::ConnectionRejected(_) => vec![],
         };
 
         commands.extend(normal_commands);
@@ -317,7 +503,8 @@ impl<
     }
 
     pub fn is_in_game(&self) -> bool {
-        self.phase == ClientLogicPhase::SendPredictedSteps
+        !self.spectator
+            && self.phase == ClientLogicPhase::SendPredictedSteps
             && self.joining_player.is_none()
             && !self.local_players.is_empty()
     }
@@ -348,10 +535,10 @@ impl<
 
         let earlier = now
             .from_lower(low_16)
-            .ok_or_else(|| ClientLogicError::MillisFromLowerError)?;
+            .ok_or(ClientLogicError::MillisFromLowerError)?;
         let duration_ms = now
             .checked_duration_since_ms(earlier)
-            .ok_or_else(|| ClientLogicError::AbsoluteTimeError)?;
+            .ok_or(ClientLogicError::AbsoluteTimeError)?;
 
         self.latency.add(
             u16::try_from(duration_ms.as_millis())
@@ -379,6 +566,8 @@ impl<
         }
 
         self.joining_player = None;
+        self.rejoining = false;
+        self.party_and_session_secret = Some(cmd.party_and_session_secret);
 
         self.local_players.clear();
 
@@ -392,6 +581,71 @@ impl<
         Ok(())
     }
 
+    /// Handles the rejection of the join game request. The join game request is not sent again
+    /// until a new joining player is set.
+    ///
+    /// # Errors
+    /// Returns [`ClientLogicError::JoinGameRejected`] with the reason from the host, or
+    /// a [`ClientLogicError`] if the rejection does not match the join game request.
+    fn on_join_game_rejected(&mut self, cmd: &JoinGameRejected) -> Result<(), ClientLogicError> {
+        if cmd.client_request_id != self.joining_request_id {
+            Err(ClientLogicError::WrongJoinResponseRequestId {
+                encountered: cmd.client_request_id,
+                expected: self.joining_request_id,
+            })?;
+        }
+
+        if self.joining_player.is_none() {
+            // The rejection of a request that has already been handled
+            return Ok(());
+        }
+
+        debug!("join game rejected: {}", cmd.reason);
+        self.joining_player = None;
+        self.rejoining = false;
+        if cmd.reason == JoinGameRejectedReason::UnknownSessionSecret {
+            self.party_and_session_secret = None;
+        }
+        self.join_rejected_reason = Some(cmd.reason);
+
+        Err(ClientLogicError::JoinGameRejected(cmd.reason))
+    }
+
+    /// Returns the reason the host gave for rejecting the last join game request, if it was rejected.
+    #[must_use]
+    pub const fn join_rejected_reason(&self) -> Option<JoinGameRejectedReason> {
+        self.join_rejected_reason
+    }
+
+    /// Handles the reception of the leave game acceptance message from the host.
+    ///
+    /// # Arguments
+    /// * `cmd`: The leave game acceptance command.
+    ///
+    /// # Errors
+    /// Returns a [`ClientLogicError`] if the response does not match the leave request.
+    fn on_leave_game(&mut self, cmd: &LeaveGameAccepted) -> Result<(), ClientLogicError> {
+        debug!("leave game accepted: {:?}", cmd);
+
+        if cmd.client_request_id != self.leaving_request_id {
+            Err(ClientLogicError::WrongLeaveResponseRequestId {
+                encountered: cmd.client_request_id,
+                expected: self.leaving_request_id,
+            })?;
+        }
+
+        self.leaving_player = None;
+
+        self.local_players.retain(|local_player| {
+            !cmd.participants
+                .0
+                .iter()
+                .any(|participant| participant.participant_id == local_player.participant_id)
+        });
+
+        Ok(())
+    }
+
     /// Returns the received game state from the host.
     ///
     /// # Returns
@@ -404,15 +658,31 @@ impl<
         self.state.as_mut()
     }
 
+    /// Returns the tick of the received game state, which is the tick of the first
+    /// authoritative step to apply to it.
+    pub const fn game_tick_id(&self) -> Option<TickId> {
+        self.state_tick_id
+    }
+
     /// Processes the game step response header received from the host.
     ///
     /// # Arguments
+    /// * `now`: The time the header was received.
     /// * `header`: The game step response header.
-    fn handle_game_step_header(&mut self, header: &GameStepResponseHeader) {
+    fn handle_game_step_header(&mut self, now: Millis, header: &GameStepResponseHeader) {
         let host_expected_tick_id = header.next_expected_tick_id;
         self.server_buffer_delta_tick_id
             .add(i16::from(header.delta_buffer));
-        //self.server_buffer_count.add(header.connection_buffer_count);
+        self.server_buffer_count.add(header.connection_buffer_count);
+        self.session_paused_at = header.session_paused_at;
+        self.session_resume_at = header
+            .session_resume_in_millis
+            .map(|resume_in_millis| now + MillisDuration::from_millis(u64::from(resume_in_millis)));
+        let is_pausing =
+            header.session_paused_at.is_some() && header.session_resume_in_millis.is_none();
+        if self.requested_session_pause == Some(is_pausing) {
+            self.requested_session_pause = None;
+        }
         trace!("removing every predicted step before {host_expected_tick_id}");
         self.outgoing_predicted_steps
             .discard_up_to(host_expected_tick_id);
@@ -437,31 +707,84 @@ impl<
             ))?;
         }
         self.phase = ClientLogicPhase::RequestDownloadState {
-            download_state_request_id: 0x99,
-        }; // TODO: proper download state request id
+            download_state_request_id: self.download_state_request_id,
+        };
         debug!("set phase to connected!");
         Ok(())
     }
 
+    /// Handles the rejection of the connect request.
+    ///
+    /// # Errors
+    /// Returns [`ClientLogicError::ConnectionRejected`] with the reason from the host, or
+    /// a [`ClientLogicError`] if the rejection does not match the connect request.
+    fn on_connection_rejected(&mut self, cmd: &ConnectionRejected) -> Result<(), ClientLogicError> {
+        if self.phase != ClientLogicPhase::RequestConnect {
+            Err(ClientLogicError::ReceivedConnectResponseWhenNotConnecting)?;
+        }
+
+        if self.connect_request_id != Some(cmd.response_to_request) {
+            Err(ClientLogicError::WrongConnectResponseRequestId(
+                cmd.response_to_request,
+            ))?;
+        }
+
+        debug!("connection rejected: {}", cmd.reason);
+        self.phase = ClientLogicPhase::ConnectionRejected(cmd.reason);
+
+        Err(ClientLogicError::ConnectionRejected(cmd.reason))
+    }
+
+    /// Returns the reason the host gave for rejecting the connection, if it was rejected.
+    pub const fn connection_rejected_reason(&self) -> Option<ConnectionRejectedReason> {
+        if let ClientLogicPhase::ConnectionRejected(reason) = self.phase {
+            Some(reason)
+        } else {
+            None
+        }
+    }
+
     /// Handles the reception of a game step response from the host.
     ///
     /// # Arguments
+    /// * `now`: The time the response was received.
     /// * `cmd`: The game step response.
     ///
     /// # Errors
-    /// Returns a `ClientErrorKind` if there are issues processing the game steps.
+    /// Returns a `ClientErrorKind` if there are issues processing the game steps, or
+    /// [`ClientLogicError::AuthoritativeStepGap`] if the first authoritative steps after a
+    /// downloaded game state do not start at the tick of that game state. The game state is
+    /// requested again then.
     fn on_game_step(
         &mut self,
+        now: Millis,
         cmd: &GameStepResponse<Step<StepT>>,
     ) -> Result<(), ClientLogicError> {
         trace!("game step response: {}", cmd);
 
-        self.handle_game_step_header(&cmd.response_header);
+        self.handle_game_step_header(now, &cmd.response_header);
 
         if cmd.authoritative_steps.ranges.is_empty() {
             return Ok(());
         }
 
+        if let Some(state_tick_id) = self.unverified_state_tick_id {
+            let first_tick_id = cmd
+                .authoritative_steps
+                .ranges
+                .iter()
+                .map(|range| range.tick_id)
+                .min()
+                .unwrap_or(state_tick_id);
+            if first_tick_id > state_tick_id {
+                self.request_download_state_again();
+                Err(ClientLogicError::AuthoritativeStepGap {
+                    state_tick_id,
+                    first_tick_id,
+                })?;
+            }
+        }
+
         let mut accepted_count = 0;
 
         for range in &cmd.authoritative_steps.ranges {
@@ -483,6 +806,7 @@ impl<
         }
 
         if accepted_count > 0 {
+            self.unverified_state_tick_id = None;
             trace!(
                 "accepted {accepted_count} auth steps, waiting for {}, total count: {}",
                 self.incoming_authoritative_steps.expected_write_tick_id(),
@@ -517,6 +841,10 @@ impl<
 
         self.phase = ClientLogicPhase::DownloadingState(download_response.tick_id);
 
+        // The first authoritative step to apply on top of the game state is the one for its tick
+        self.incoming_authoritative_steps = Queue::new(download_response.tick_id);
+        self.unverified_state_tick_id = Some(download_response.tick_id);
+
         Ok(())
     }
 
@@ -526,19 +854,27 @@ impl<
     /// * `blob_stream_command`: The blob stream command from the host.
     ///
     /// # Errors
-    /// Returns a `ClientErrorKind` if the blob stream command is unexpected.
+    /// Returns a `ClientErrorKind` if the blob stream command is unexpected. If the received
+    /// game state does not match its checksum, the game state is requested again and
+    /// [`FrontLogicError::ChecksumMismatch`] is returned.
     fn on_blob_stream(
         &mut self,
         blob_stream_command: &SenderToReceiverFrontCommands,
     ) -> Result<(), ClientLogicError> {
         match self.phase {
-            ClientLogicPhase::DownloadingState(_) => {
-                self.blob_stream_client.receive(blob_stream_command)?;
+            ClientLogicPhase::DownloadingState(state_tick_id) => {
+                if let Err(err) = self.blob_stream_client.receive(blob_stream_command) {
+                    if matches!(err, FrontLogicError::ChecksumMismatch { .. }) {
+                        self.request_download_state_again();
+                    }
+                    Err(err)?;
+                }
                 if let Some(blob_ready) = self.blob_stream_client.blob() {
                     debug!("blob stream received, phase is set to SendPredictedSteps");
                     self.phase = ClientLogicPhase::SendPredictedSteps;
                     let (deserialized, _) = StateT::deserialize(blob_ready)?;
                     self.state = Some(deserialized);
+                    self.state_tick_id = Some(state_tick_id);
                 }
             }
             _ => Err(ClientLogicError::UnexpectedBlobChannelCommand)?,
@@ -546,6 +882,21 @@ impl<
         Ok(())
     }
 
+    /// Drops the game state, or its transfer, and requests the game state again, with a new
+    /// request id so a late response to the previous request is not mistaken for it.
+    fn request_download_state_again(&mut self) {
+        debug!("game state can not be used, requesting it again");
+        self.blob_stream_client = FrontLogic::new();
+        self.incoming_authoritative_steps = Queue::default();
+        self.unverified_state_tick_id = None;
+        self.state = None;
+        self.state_tick_id = None;
+        self.download_state_request_id = self.download_state_request_id.wrapping_add(1);
+        self.phase = ClientLogicPhase::RequestDownloadState {
+            download_state_request_id: self.download_state_request_id,
+        };
+    }
+
     /// Receives a command from the host and processes it accordingly.
     ///
     /// # Arguments
@@ -563,7 +914,7 @@ impl<
                 self.on_join_game(join_game_response)
             }
             HostToClientCommands::GameStep(ref game_step_response) => {
-                self.on_game_step(game_step_response)
+                self.on_game_step(now, game_step_response)
             }
             HostToClientCommands::DownloadGameState(ref download_response) => {
                 self.on_download_state_response(download_response)
@@ -575,6 +926,15 @@ impl<
                 self.on_connect(connect_accepted)
             }
             HostToClientCommands::Pong(pong_info) => self.on_pong(now, pong_info),
+            HostToClientCommands::LeaveGame(ref leave_game_response) => {
+                self.on_leave_game(leave_game_response)
+            }
+            HostToClientCommands::JoinGameRejected(ref join_game_rejected) => {
+                self.on_join_game_rejected(join_game_rejected)
+            }
+            HostToClientCommands::ConnectionRejected(ref connection_rejected) => {
+                self.on_connection_rejected(connection_rejected)
+            }
         }
     }
 
@@ -588,6 +948,16 @@ impl<
             .map(|value| value.round() as i16)
     }
 
+    /// Returns the average number of steps buffered on the server for this connection, if available.
+    ///
+    /// # Returns
+    /// An optional average server buffer step count.
+    pub fn server_buffer_count(&self) -> Option<u8> {
+        self.server_buffer_count
+            .average()
+            .map(|value| value.round() as u8)
+    }
+
     pub fn local_players(&self) -> Vec<LocalPlayer> {
         self.local_players.clone()
     }
@@ -4,14 +4,25 @@
  */
 use flood_rs::{BufferDeserializer, Deserialize, Serialize};
 use monotonic_time_rs::Millis;
+use nimble_blob_stream::checksum::crc32;
+use nimble_blob_stream::prelude::{
+    FrontLogicError, SenderToReceiverFrontCommands, SetChunkData, SetChunkFrontData,
+    StartTransferData, TransferId,
+};
 use nimble_client_logic::err::ClientLogicError;
 use nimble_client_logic::{ClientLogic, ClientLogicPhase};
 use nimble_participant::ParticipantId;
-use nimble_protocol::client_to_host::{ConnectRequest, DownloadGameStateRequest};
+use nimble_protocol::client_to_host::{
+    ConnectRequest, DownloadGameStateRequest, JoinGameRequest, JoinGameType, SessionPauseRequest,
+};
 use nimble_protocol::host_to_client::{
-    AuthoritativeStepRanges, ConnectionAccepted, GameStepResponse, GameStepResponseHeader,
+    AuthoritativeStepRanges, ConnectionAccepted, ConnectionRejected, ConnectionRejectedReason,
+    DownloadGameStateResponse, GameStepResponse, GameStepResponseHeader, JoinGameAccepted,
+    JoinGameParticipant, JoinGameParticipants, JoinGameRejected, JoinGameRejectedReason,
+    LeaveGameAccepted, PartyAndSessionSecret,
 };
 use nimble_protocol::prelude::{ClientToHostCommands, CombinedSteps, HostToClientCommands};
+use nimble_protocol::{ClientRequestId, SessionConnectionSecret};
 use nimble_sample_step::{SampleState, SampleStep};
 use nimble_step::Step;
 use nimble_step::Step::{Custom, Forced};
@@ -40,7 +51,9 @@ fn basic_logic() {
     }
 }
 
-fn feed_connect_response(client_logic: &mut ClientLogic<SampleState, Step<SampleStep>>) {
+fn feed_connect_response<StepT: Clone + Deserialize + Serialize + Debug + std::fmt::Display>(
+    client_logic: &mut ClientLogic<SampleState, StepT>,
+) {
     let now = Millis::new(0);
 
     let commands = client_logic.send(now);
@@ -105,14 +118,14 @@ fn send_steps() -> Result<(), ClientLogicError> {
 }
 
 fn setup_sample_steps() -> AuthoritativeStepRanges<Step<SampleStep>> {
-    let first_steps = vec![
+    let first_steps = [
         Custom(SampleStep::Jump),
         Custom(SampleStep::MoveLeft(-10)),
         Custom(SampleStep::MoveRight(32000)),
     ];
     let first_participant_id = ParticipantId(255);
 
-    let second_steps = vec![
+    let second_steps = [
         Custom(SampleStep::MoveLeft(42)),
         Forced,
         Custom(SampleStep::Jump),
@@ -137,11 +150,9 @@ fn setup_sample_steps() -> AuthoritativeStepRanges<Step<SampleStep>> {
         steps: auth_steps,
     };
 
-    let ranges_to_send = AuthoritativeStepRanges {
+    AuthoritativeStepRanges {
         ranges: vec![range_to_send],
-    };
-
-    ranges_to_send
+    }
 }
 #[test_log::test]
 fn receive_authoritative_steps() -> Result<(), ClientLogicError> {
@@ -154,6 +165,8 @@ fn receive_authoritative_steps() -> Result<(), ClientLogicError> {
             connection_buffer_count: 2,
             delta_buffer: -2,
             next_expected_tick_id: TickId(0),
+            session_paused_at: None,
+            session_resume_in_millis: None,
         },
         authoritative_steps: setup_sample_steps(),
     };
@@ -204,6 +217,8 @@ fn receive_authoritative_steps() -> Result<(), ClientLogicError> {
             connection_buffer_count: 2,
             delta_buffer: -3,
             next_expected_tick_id: TickId(0),
+            session_paused_at: None,
+            session_resume_in_millis: None,
         },
         authoritative_steps: setup_sample_steps(),
     };
@@ -220,6 +235,8 @@ fn receive_authoritative_steps() -> Result<(), ClientLogicError> {
             connection_buffer_count: 8,
             delta_buffer: -4,
             next_expected_tick_id: TickId(0),
+            session_paused_at: None,
+            session_resume_in_millis: None,
         },
         authoritative_steps: setup_sample_steps(),
     };
@@ -250,6 +267,7 @@ fn create_connecting_client(
 #[test_log::test]
 fn send_connect_command() {
     let mut client = create_connecting_client(None);
+    client.set_client_token(b"ticket");
     let now = Millis::new(0);
 
     let commands = client.send(now);
@@ -270,10 +288,11 @@ fn send_connect_command() {
         nimble_protocol::Version {
             major: 0,
             minor: 0,
-            patch: 5
+            patch: 9
         }
     );
-    assert_eq!(connect_cmd.use_debug_stream, false);
+    assert!(!connect_cmd.use_debug_stream);
+    assert_eq!(connect_cmd.client_token, b"ticket");
     assert_eq!(
         connect_cmd.client_request_id,
         client
@@ -351,3 +370,461 @@ fn receive_response_without_request() {
         _ => panic!("Expected WrongConnectResponseNonce error {result:?}"),
     }
 }
+
+#[test_log::test]
+fn leave_game() -> Result<(), ClientLogicError> {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    feed_connect_response(&mut client_logic);
+    let now = Millis::new(0);
+
+    client_logic.set_joining_player(&[0, 1]);
+    let join_accepted = JoinGameAccepted {
+        client_request_id: ClientRequestId(0),
+        party_and_session_secret: PartyAndSessionSecret {
+            session_secret: SessionConnectionSecret { value: 0 },
+            party_id: 0,
+        },
+        participants: JoinGameParticipants(vec![
+            JoinGameParticipant {
+                local_index: 0,
+                participant_id: ParticipantId(4),
+            },
+            JoinGameParticipant {
+                local_index: 1,
+                participant_id: ParticipantId(5),
+            },
+        ]),
+    };
+    client_logic.receive(now, &HostToClientCommands::JoinGame(join_accepted))?;
+    assert_eq!(client_logic.local_players().len(), 2);
+
+    client_logic.set_leaving_player(&[1]);
+    let commands = client_logic.send(now);
+    let leave_request = commands
+        .iter()
+        .find_map(|command| match command {
+            ClientToHostCommands::LeaveGameType(leave_request) => Some(leave_request),
+            _ => None,
+        })
+        .expect("should send a leave request");
+    assert_eq!(leave_request.local_indices, [1]);
+
+    let leave_accepted = LeaveGameAccepted {
+        client_request_id: leave_request.client_request_id,
+        participants: JoinGameParticipants(vec![JoinGameParticipant {
+            local_index: 1,
+            participant_id: ParticipantId(5),
+        }]),
+    };
+    client_logic.receive(now, &HostToClientCommands::LeaveGame(leave_accepted))?;
+
+    let local_players = client_logic.local_players();
+    assert_eq!(local_players.len(), 1);
+    assert_eq!(local_players[0].participant_id, ParticipantId(4));
+
+    let commands = client_logic.send(now);
+    assert!(!commands
+        .iter()
+        .any(|command| matches!(command, ClientToHostCommands::LeaveGameType(_))));
+
+    Ok(())
+}
+
+#[test_log::test]
+fn session_pause() -> Result<(), ClientLogicError> {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    feed_connect_response(&mut client_logic);
+    let now = Millis::new(0);
+
+    client_logic.request_session_pause(true);
+    let session_pause_request = |commands: Vec<ClientToHostCommands<Step<SampleStep>>>| {
+        commands.into_iter().find_map(|command| match command {
+            ClientToHostCommands::SessionPause(request) => Some(request),
+            _ => None,
+        })
+    };
+    assert_eq!(
+        session_pause_request(client_logic.send(now)),
+        Some(SessionPauseRequest { paused: true })
+    );
+
+    let response = GameStepResponse {
+        response_header: GameStepResponseHeader {
+            connection_buffer_count: 0,
+            delta_buffer: 0,
+            next_expected_tick_id: TickId(5),
+            session_paused_at: Some(TickId(5)),
+            session_resume_in_millis: None,
+        },
+        authoritative_steps: AuthoritativeStepRanges { ranges: vec![] },
+    };
+    client_logic.receive(now, &HostToClientCommands::GameStep(response))?;
+
+    assert_eq!(client_logic.session_paused_at(), Some(TickId(5)));
+    assert_eq!(session_pause_request(client_logic.send(now)), None);
+
+    client_logic.request_session_pause(false);
+    assert_eq!(
+        session_pause_request(client_logic.send(now)),
+        Some(SessionPauseRequest { paused: false })
+    );
+
+    let response = GameStepResponse {
+        response_header: GameStepResponseHeader {
+            connection_buffer_count: 0,
+            delta_buffer: 0,
+            next_expected_tick_id: TickId(5),
+            session_paused_at: Some(TickId(5)),
+            session_resume_in_millis: Some(128),
+        },
+        authoritative_steps: AuthoritativeStepRanges { ranges: vec![] },
+    };
+    let received_at = Millis::new(1000);
+    client_logic.receive(received_at, &HostToClientCommands::GameStep(response))?;
+
+    assert_eq!(client_logic.session_resume_at(), Some(Millis::new(1128)));
+    assert_eq!(session_pause_request(client_logic.send(now)), None);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn spectator_does_not_join() {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    client_logic.set_spectator(true);
+    feed_connect_response(&mut client_logic);
+
+    client_logic.set_joining_player(&[0]);
+    let commands = client_logic.send(Millis::new(0));
+    assert!(!commands
+        .iter()
+        .any(|command| matches!(command, ClientToHostCommands::JoinGameType(_))));
+    assert!(!client_logic.is_in_game());
+}
+
+#[test_log::test]
+fn reconnect_joins_with_session_secret() -> Result<(), ClientLogicError> {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    feed_connect_response(&mut client_logic);
+    let now = Millis::new(0);
+
+    let party_and_session_secret = PartyAndSessionSecret {
+        session_secret: SessionConnectionSecret {
+            value: 0x1234_5678_9ABC_DEF0,
+        },
+        party_id: 3,
+    };
+    client_logic.set_joining_player(&[0]);
+    let commands = client_logic.send(now);
+    assert!(commands.iter().any(|command| matches!(
+        command,
+        ClientToHostCommands::JoinGameType(JoinGameRequest {
+            join_game_type: JoinGameType::NoSecret,
+            ..
+        })
+    )));
+
+    let join_accepted = JoinGameAccepted {
+        client_request_id: ClientRequestId(0),
+        party_and_session_secret,
+        participants: JoinGameParticipants(vec![JoinGameParticipant {
+            local_index: 0,
+            participant_id: ParticipantId(7),
+        }]),
+    };
+    client_logic.receive(now, &HostToClientCommands::JoinGame(join_accepted))?;
+    assert_eq!(
+        client_logic.party_and_session_secret(),
+        Some(&party_and_session_secret)
+    );
+
+    client_logic.reconnect();
+    assert_eq!(client_logic.phase(), &ClientLogicPhase::RequestConnect);
+    feed_connect_response(&mut client_logic);
+
+    let commands = client_logic.send(now);
+    let join_request = commands
+        .iter()
+        .find_map(|command| match command {
+            ClientToHostCommands::JoinGameType(join_request) => Some(join_request),
+            _ => None,
+        })
+        .expect("should join again after reconnect");
+    assert_eq!(
+        join_request.join_game_type,
+        JoinGameType::UseSessionSecret(party_and_session_secret.session_secret)
+    );
+    assert_eq!(join_request.player_requests.players.len(), 1);
+    assert_eq!(join_request.player_requests.players[0].local_index, 0);
+
+    Ok(())
+}
+
+#[test_log::test]
+fn connection_rejected() {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    let now = Millis::new(0);
+
+    let commands = client_logic.send(now);
+    let ClientToHostCommands::ConnectType(ConnectRequest {
+        client_request_id, ..
+    }) = commands[0]
+    else {
+        panic!("Command should be connect request");
+    };
+
+    let result = client_logic.receive(
+        now,
+        &HostToClientCommands::ConnectionRejected(ConnectionRejected {
+            response_to_request: client_request_id,
+            reason: ConnectionRejectedReason::ApplicationVersionMismatch,
+        }),
+    );
+    assert!(matches!(
+        result,
+        Err(ClientLogicError::ConnectionRejected(
+            ConnectionRejectedReason::ApplicationVersionMismatch
+        ))
+    ));
+    assert_eq!(
+        client_logic.connection_rejected_reason(),
+        Some(ConnectionRejectedReason::ApplicationVersionMismatch)
+    );
+
+    // Stops sending connect requests
+    assert!(client_logic.send(now).is_empty());
+}
+
+#[test_log::test]
+fn join_game_rejected() {
+    let mut client_logic = setup_logic::<SampleState, Step<SampleStep>>();
+    feed_connect_response(&mut client_logic);
+    let now = Millis::new(0);
+
+    let is_joining = |commands: &[ClientToHostCommands<Step<SampleStep>>]| {
+        commands
+            .iter()
+            .any(|command| matches!(command, ClientToHostCommands::JoinGameType(_)))
+    };
+
+    client_logic.set_joining_player(&[0, 1]);
+    assert!(is_joining(&client_logic.send(now)));
+
+    let result = client_logic.receive(
+        now,
+        &HostToClientCommands::JoinGameRejected(JoinGameRejected {
+            client_request_id: ClientRequestId(0),
+            reason: JoinGameRejectedReason::TooManyLocalPlayers,
+        }),
+    );
+    assert!(matches!(
+        result,
+        Err(ClientLogicError::JoinGameRejected(
+            JoinGameRejectedReason::TooManyLocalPlayers
+        ))
+    ));
+    assert_eq!(
+        client_logic.join_rejected_reason(),
+        Some(JoinGameRejectedReason::TooManyLocalPlayers)
+    );
+
+    // Stops sending the join game request
+    assert!(!is_joining(&client_logic.send(now)));
+
+    client_logic.set_joining_player(&[0]);
+    assert_eq!(client_logic.join_rejected_reason(), None);
+    assert!(is_joining(&client_logic.send(now)));
+}
+
+fn game_step_response_at(tick_id: TickId) -> HostToClientCommands<Step<SampleStep>> {
+    let mut authoritative_steps = setup_sample_steps();
+    authoritative_steps.ranges[0].tick_id = tick_id;
+
+    HostToClientCommands::GameStep(GameStepResponse::<Step<SampleStep>> {
+        response_header: GameStepResponseHeader {
+            connection_buffer_count: 0,
+            delta_buffer: 0,
+            next_expected_tick_id: tick_id,
+            session_paused_at: None,
+            session_resume_in_millis: None,
+        },
+        authoritative_steps,
+    })
+}
+
+#[test_log::test]
+fn authoritative_steps_must_start_at_downloaded_state_tick() -> Result<(), ClientLogicError> {
+    let mut client_logic = setup_logic::<SampleState, SampleStep>();
+    let now = Millis::new(0);
+
+    feed_connect_response(&mut client_logic);
+
+    let ClientLogicPhase::RequestDownloadState {
+        download_state_request_id,
+    } = *client_logic.phase()
+    else {
+        panic!("should request the game state");
+    };
+
+    client_logic.receive(
+        now,
+        &HostToClientCommands::DownloadGameState(DownloadGameStateResponse {
+            client_request: download_state_request_id,
+            tick_id: TickId(10),
+            blob_stream_channel: 1,
+        }),
+    )?;
+
+    assert_eq!(
+        client_logic
+            .debug_authoritative_steps()
+            .expected_write_tick_id(),
+        TickId(10)
+    );
+
+    // The steps for tick 10 and 11 are missing
+    let err = client_logic
+        .receive(now, &game_step_response_at(TickId(12)))
+        .expect_err("should report the gap");
+    assert!(matches!(
+        err,
+        ClientLogicError::AuthoritativeStepGap {
+            state_tick_id: TickId(10),
+            first_tick_id: TickId(12),
+        }
+    ));
+    assert!(client_logic.debug_authoritative_steps().is_empty());
+
+    // The game state is requested again, and the steps after it are received
+    let ClientLogicPhase::RequestDownloadState {
+        download_state_request_id: new_download_state_request_id,
+    } = *client_logic.phase()
+    else {
+        panic!("should request the game state again");
+    };
+    assert_ne!(new_download_state_request_id, download_state_request_id);
+    assert!(client_logic.send(now).iter().any(|command| matches!(
+        command,
+        ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id })
+            if *request_id == new_download_state_request_id
+    )));
+
+    client_logic.receive(
+        now,
+        &HostToClientCommands::DownloadGameState(DownloadGameStateResponse {
+            client_request: new_download_state_request_id,
+            tick_id: TickId(12),
+            blob_stream_channel: 2,
+        }),
+    )?;
+
+    // Ticks 10 and 11 are before the game state, so only tick 12 is accepted
+    client_logic.receive(now, &game_step_response_at(TickId(10)))?;
+    let authoritative_steps = client_logic.debug_authoritative_steps();
+    assert_eq!(authoritative_steps.len(), 1);
+    assert_eq!(authoritative_steps.front_tick_id(), Some(TickId(12)));
+
+    // Once the state tick has been received, later steps are only ignored until they are in order
+    client_logic.receive(now, &game_step_response_at(TickId(20)))?;
+    assert_eq!(client_logic.debug_authoritative_steps().len(), 1);
+    client_logic.receive(now, &game_step_response_at(TickId(13)))?;
+    assert_eq!(client_logic.debug_authoritative_steps().len(), 4);
+
+    Ok(())
+}
+
+fn receive_game_state(
+    client_logic: &mut ClientLogic<SampleState, SampleStep>,
+    download_state_request_id: u8,
+    transfer_id: u16,
+    checksum: u32,
+    game_state: &[u8],
+) -> Result<(), ClientLogicError> {
+    let now = Millis::new(0);
+
+    client_logic.receive(
+        now,
+        &HostToClientCommands::DownloadGameState(DownloadGameStateResponse {
+            client_request: download_state_request_id,
+            tick_id: TickId(10),
+            blob_stream_channel: transfer_id,
+        }),
+    )?;
+    client_logic.receive(
+        now,
+        &HostToClientCommands::BlobStreamChannel(SenderToReceiverFrontCommands::StartTransfer(
+            StartTransferData {
+                transfer_id,
+                total_octet_size: game_state.len() as u32,
+                chunk_size: 64,
+                checksum,
+            },
+        )),
+    )?;
+    client_logic.receive(
+        now,
+        &HostToClientCommands::BlobStreamChannel(SenderToReceiverFrontCommands::SetChunk(
+            SetChunkFrontData {
+                transfer_id: TransferId(transfer_id),
+                data: SetChunkData {
+                    chunk_index: 0,
+                    payload: game_state.to_vec(),
+                },
+            },
+        )),
+    )
+}
+
+#[test_log::test]
+fn corrupt_game_state_is_downloaded_again() -> Result<(), ClientLogicError> {
+    let mut client_logic = setup_logic::<SampleState, SampleStep>();
+    let game_state = [0x10, 0x20, 0x30, 0x40];
+
+    feed_connect_response(&mut client_logic);
+
+    let ClientLogicPhase::RequestDownloadState {
+        download_state_request_id,
+    } = *client_logic.phase()
+    else {
+        panic!("should request the game state");
+    };
+
+    let err = receive_game_state(
+        &mut client_logic,
+        download_state_request_id,
+        1,
+        crc32(&game_state),
+        &[0x10, 0x20, 0x31, 0x40],
+    )
+    .expect_err("a corrupt game state should be rejected");
+    assert!(matches!(
+        err,
+        ClientLogicError::FrontLogicErr(FrontLogicError::ChecksumMismatch { .. })
+    ));
+
+    let ClientLogicPhase::RequestDownloadState {
+        download_state_request_id: new_download_state_request_id,
+    } = *client_logic.phase()
+    else {
+        panic!("should request the game state again");
+    };
+    assert_ne!(new_download_state_request_id, download_state_request_id);
+
+    let commands = client_logic.send(Millis::new(0));
+    assert!(commands.iter().any(|command| matches!(
+        command,
+        ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id })
+            if *request_id == new_download_state_request_id
+    )));
+
+    receive_game_state(
+        &mut client_logic,
+        new_download_state_request_id,
+        2,
+        crc32(&game_state),
+        &game_state,
+    )?;
+    assert_eq!(*client_logic.phase(), ClientLogicPhase::SendPredictedSteps);
+
+    Ok(())
+}
@@ -20,6 +20,7 @@ pub enum ClientError {
     DatagramChunkerError(DatagramChunkerError),
     NimbleLayerError(NimbleLayerError),
     PredictionQueueOverflow,
+    SpectatorCanNotJoin,
     SeqMapError(SeqMapError),
 }
 
@@ -55,6 +56,7 @@ impl ErrorLevelProvider for ClientError {
             | Self::QueueError(_)
             | Self::DatagramChunkerError(_)
             | Self::NimbleLayerError(_) => ErrorLevel::Info,
+            Self::SpectatorCanNotJoin => ErrorLevel::Warning,
             Self::RectifyError(err) => err.error_level(),
             Self::PredictionQueueOverflow | Self::SeqMapError(_) => ErrorLevel::Critical,
         }
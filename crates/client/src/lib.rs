@@ -17,9 +17,9 @@ receiving authoritative steps to ensure consistent game state.
 - **Participant Management:** Add and remove players by sending requests to the host. ➕➖
 - **Input Prediction:** Send predicted inputs (steps) to the host for reduced latency. 🔮
 - **Authoritative Step Handling:** Receive and apply authoritative steps from the host to
-    maintain game state consistency. 📥📤
+  maintain game state consistency. 📥📤
 - **Metrics and Logging:** Built-in support for network metrics and logging to monitor and
-    debug client operations. 📊🛠️
+  debug client operations. 📊🛠️
 
 */
 
@@ -37,7 +37,9 @@ use nimble_client_logic::err::ClientLogicError;
 use nimble_client_logic::LocalIndex;
 use nimble_client_logic::{ClientLogic, ClientLogicPhase, LocalPlayer};
 use nimble_layer::NimbleLayer;
-use nimble_protocol::prelude::HostToClientCommands;
+use nimble_protocol::prelude::{
+    ConnectionRejectedReason, HostToClientCommands, JoinGameRejectedReason, SessionId,
+};
 use nimble_rectify::{Rectify, RectifyCallbacks};
 use nimble_step::Step;
 use nimble_step_map::StepMap;
@@ -149,6 +151,33 @@ impl<
         }
     }
 
+    /// Sets the opaque token that is sent in the connect request, so the host can authenticate
+    /// the client, e.g. with a ticket issued by a backend.
+    #[must_use]
+    pub fn with_client_token(mut self, client_token: &[u8]) -> Self {
+        self.logic.set_client_token(client_token);
+        self
+    }
+
+    /// Sets the game session on the host to connect to, for hosts that run several sessions.
+    #[must_use]
+    pub fn with_session_id(mut self, session_id: SessionId) -> Self {
+        self.logic.set_session_id(session_id);
+        self
+    }
+
+    /// Makes the client a spectator. It downloads the game state and follows the authoritative
+    /// steps from the host, but never joins any players or predicts any steps.
+    #[must_use]
+    pub fn with_spectator(mut self) -> Self {
+        self.logic.set_spectator(true);
+        self
+    }
+
+    pub const fn is_spectator(&self) -> bool {
+        self.logic.is_spectator()
+    }
+
     #[must_use]
     pub const fn with_tick_duration(mut self, tick_duration: MillisDuration) -> Self {
         self.tick_duration_ms = tick_duration;
@@ -246,6 +275,22 @@ impl<
             .set_tick_duration(*factor * self.tick_duration_ms);
         self.authoritative_time_tick.calculate_ticks(now);
 
+        if self.logic.phase() == &ClientLogicPhase::SendPredictedSteps
+            && self.phase != ClientPhase::CanSendPredicted
+        {
+            // The first authoritative step is the one for the tick of the downloaded game state
+            if let Some(game_tick_id) = self.logic.game_tick_id() {
+                self.rectify.reset_authoritative(game_tick_id);
+            }
+            self.prediction_time_tick.reset(now);
+            self.phase = ClientPhase::CanSendPredicted;
+        } else if self.logic.phase() != &ClientLogicPhase::SendPredictedSteps
+            && self.phase == ClientPhase::CanSendPredicted
+        {
+            // The game state is downloaded again
+            self.phase = ClientPhase::Normal;
+        }
+
         let (first_tick_id_in_vector, auth_steps) = self.logic.pop_all_authoritative_steps();
         let mut current_tick_id = first_tick_id_in_vector;
         for auth_step in auth_steps {
@@ -256,15 +301,13 @@ impl<
             current_tick_id = TickId(current_tick_id.0 + 1);
         }
 
-        if self.logic.phase() == &ClientLogicPhase::SendPredictedSteps
-            && self.phase != ClientPhase::CanSendPredicted
-        {
-            self.prediction_time_tick.reset(now);
-            self.phase = ClientPhase::CanSendPredicted;
-        }
-
         match self.phase {
             ClientPhase::Normal => {}
+            ClientPhase::CanSendPredicted if self.logic.is_spectator() => {
+                if let Some(game) = self.logic.game_mut() {
+                    self.rectify.update_authoritative(game);
+                }
+            }
             ClientPhase::CanSendPredicted => {
                 self.adjust_prediction_ticker();
                 self.last_need_prediction_count = self.prediction_time_tick.calculate_ticks(now);
@@ -275,6 +318,14 @@ impl<
                     );
                     self.last_need_prediction_count = 0;
                     self.prediction_time_tick.reset(now);
+                } else if let Some(paused_at) = self.logic.session_paused_at().filter(|_| {
+                    self.logic
+                        .session_resume_at()
+                        .is_none_or(|resume_at| now < resume_at)
+                }) {
+                    trace!("session is paused at {paused_at}, no predictions needed");
+                    self.last_need_prediction_count = 0;
+                    self.prediction_time_tick.reset(now);
                 }
 
                 trace!("prediction count: {}", self.last_need_prediction_count);
@@ -378,13 +429,13 @@ impl<
         }
     }
 
-    /// Checks if a new player can join the game session.
+    /// Checks if a new player can join the game session. Spectators can never join players.
     ///
     /// # Returns
     ///
     /// `true` if a player can join, `false` otherwise.
     pub const fn can_join_player(&self) -> bool {
-        self.game().is_some()
+        !self.logic.is_spectator() && self.game().is_some()
     }
 
     /// Retrieves a list of local players currently managed by the client.
@@ -464,6 +515,52 @@ impl<
         self.logic.server_buffer_delta_ticks()
     }
 
+    /// Retrieves the reason the host gave for rejecting the connection, if it was rejected.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the `ConnectionRejectedReason`, or `None` if the connection was not rejected.
+    pub const fn connection_rejected_reason(&self) -> Option<ConnectionRejectedReason> {
+        self.logic.connection_rejected_reason()
+    }
+
+    /// Retrieves the reason the host gave for rejecting the last join game request, if it was rejected.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the `JoinGameRejectedReason`, or `None` if the join was not rejected.
+    pub const fn join_rejected_reason(&self) -> Option<JoinGameRejectedReason> {
+        self.logic.join_rejected_reason()
+    }
+
+    /// Retrieves the average number of steps buffered on the server for this client.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the buffered step count as `u8`, or `None` if unavailable.
+    pub fn server_buffer_count(&self) -> Option<u8> {
+        self.logic.server_buffer_count()
+    }
+
+    /// Starts over on a new connection to the host, e.g. after the previous connection was lost.
+    ///
+    /// The game state is downloaded again and the local players are automatically joined again
+    /// using the party secret received from the host, so they keep their participant ids.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time in milliseconds.
+    pub fn reconnect(&mut self, now: Millis) {
+        self.logic.reconnect();
+        self.nimble_layer = NimbleLayer::new();
+        self.metrics = NetworkMetrics::new(now);
+        self.rectify = Rectify::new(self.rectify.settings());
+        self.authoritative_time_tick.reset(now);
+        self.prediction_time_tick.reset(now);
+        self.last_need_prediction_count = 0;
+        self.phase = ClientPhase::Normal;
+    }
+
     /// Requests to join a new player with the specified local indices.
     ///
     /// This method sends a request to the host to add new participants to the game session.
@@ -477,12 +574,58 @@ impl<
     /// A `Result` indicating success or containing a `ClientError`.
     /// # Errors
     ///
-    /// `ClientError` // TODO:
+    /// `ClientError::SpectatorCanNotJoin` if the client is a spectator.
     pub fn request_join_player(
         &mut self,
         local_players: &[LocalIndex],
     ) -> Result<(), ClientError> {
+        if self.logic.is_spectator() {
+            return Err(ClientError::SpectatorCanNotJoin);
+        }
         self.logic.set_joining_player(local_players);
         Ok(())
     }
+
+    /// Requests that the players with the specified local indices leave the game.
+    ///
+    /// This method sends a request to the host to remove the participants from the game session.
+    ///
+    /// # Arguments
+    ///
+    /// * `local_players` - The `LocalIndex` of each local player that should leave.
+    pub fn request_leave_player(&mut self, local_players: &[LocalIndex]) {
+        self.logic.set_leaving_player(local_players);
+    }
+
+    /// Requests that the host pauses or resumes the game session.
+    ///
+    /// The host only accepts the request if it allows clients to pause and this client has
+    /// joined players. The session resumes from the same tick that it was paused at.
+    ///
+    /// # Arguments
+    ///
+    /// * `paused` - `true` to pause the session, `false` to resume it.
+    pub fn request_session_pause(&mut self, paused: bool) {
+        self.logic.request_session_pause(paused);
+    }
+
+    /// Retrieves the tick that the game session is paused at.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the `TickId` that no authoritative steps are produced from,
+    /// or `None` if the session is running.
+    pub const fn session_paused_at(&self) -> Option<TickId> {
+        self.logic.session_paused_at()
+    }
+
+    /// Retrieves the time when the host resumes the paused game session.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the time that the host resumes at. Predictions start again
+    /// when it is reached, or `None` if no resume is scheduled.
+    pub const fn session_resume_at(&self) -> Option<Millis> {
+        self.logic.session_resume_at()
+    }
 }
@@ -9,4 +9,6 @@ pub use {
     nimble_rectify::{RectifyCallback, RectifyCallbacks},
     nimble_seer::SeerCallback,
     nimble_client_logic::{LocalIndex},
+    nimble_participant::HOST_PARTICIPANT_ID,
+    nimble_protocol::prelude::{ConnectionRejectedReason, JoinGameRejectedReason, SessionId},
 };
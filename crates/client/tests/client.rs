@@ -10,7 +10,8 @@ use monotonic_time_rs::{Millis, MillisDuration};
 use nimble_client::{err::ClientError, Client, GameCallbacks};
 use nimble_host::prelude::HostError;
 use nimble_host::Host;
-use nimble_host_logic::{GameStateProvider, HostConnectionId};
+use nimble_host_logic::bot::Bot;
+use nimble_host_logic::{GameStateProvider, HostConnectionId, DEFAULT_SESSION_ID};
 use nimble_participant::ParticipantId;
 use nimble_sample_game::{SampleGame, SampleGameState};
 use nimble_sample_step::SampleStep;
@@ -123,11 +124,8 @@ fn communicate<
             } else {
                 let error = to_client_datagrams_result.err().unwrap();
                 log_err(&error);
-                match error.error_level() {
-                    ErrorLevel::Critical => {
-                        return Err(error);
-                    }
-                    _ => {}
+                if error.error_level() == ErrorLevel::Critical {
+                    return Err(error);
                 }
             }
         }
@@ -208,7 +206,8 @@ fn client_to_host() -> Result<(), ClientError> {
     // let host_connection = host.get_stream(connection_id).expect("should find connection");
     // let x = host.session().participants.get(&ParticipantId(0)).expect("should find participant");
 
-    let expected_game_state = SampleGameState { x: 84, y: 42 };
+    // The first authoritative step for the participant is `Step::Joined`, not a move
+    let expected_game_state = SampleGameState { x: 83, y: 42 };
 
     assert_eq!(
         client
@@ -229,10 +228,218 @@ fn client_to_host() -> Result<(), ClientError> {
     );
 
     assert_eq_with_epsilon(client.metrics().outgoing.datagrams_per_second, 62.5, 0.001);
-    assert_eq!(client.metrics().outgoing.octets_per_second, 2821.4285); // 2.8 Kbps
+    assert_eq!(client.metrics().outgoing.octets_per_second, 2794.6428); // 2.8 Kbps
 
     assert_eq_with_epsilon(client.metrics().incoming.datagrams_per_second, 53.57, 0.01);
-    assert_eq!(client.metrics().incoming.octets_per_second, 19928.57); // 103 kbps. (normal maximum is 120 Kbps, extreme is 575 Kbps)
+    assert_eq!(client.metrics().incoming.octets_per_second, 4071.4285); // 32 kbps. (normal maximum is 120 Kbps, extreme is 575 Kbps)
+
+    Ok(())
+}
+
+fn exchange<GameT: GameCallbacks<SampleStep> + Debug>(
+    host: &mut Host<SampleStep>,
+    state_provider: &impl GameStateProvider,
+    connection_id: HostConnectionId,
+    client: &mut Client<GameT, SampleStep>,
+    now: Millis,
+) -> Result<(), ClientError> {
+    for to_host_datagram in client.send(now)? {
+        for to_client_datagram in host
+            .update(connection_id, now, &to_host_datagram, state_provider)
+            .expect("host should handle datagram")
+        {
+            if let Err(err) = client.receive(now, &to_client_datagram) {
+                log_err(&err);
+            }
+        }
+    }
+
+    for to_client_datagram in host.send(connection_id, now).expect("host should send") {
+        if let Err(err) = client.receive(now, &to_client_datagram) {
+            log_err(&err);
+        }
+    }
+
+    client.update(now)
+}
+
+#[test_log::test]
+fn spectator_follows_authoritative_steps() -> Result<(), ClientError> {
+    let mut now = Millis::new(0);
+    let mut player = Client::<SampleGame, SampleStep>::new(now);
+    let mut spectator = Client::<SampleGame, SampleStep>::new(now).with_spectator();
+    assert!(spectator.is_spectator());
+
+    let mut host = Host::<SampleStep>::new(SampleGame::version(), TickId::new(0));
+    let player_connection_id = host.create_connection().expect("should work");
+    let spectator_connection_id = host.create_connection().expect("should work");
+
+    let initial_game_state = SampleGameState { x: -11, y: 42 };
+    let state_provider = TestStateProvider {
+        tick_id: TickId(0),
+        payload: initial_game_state.to_octets()?,
+    };
+
+    let mut predicted_tick_id = TickId(0);
+    for _ in 0..60 {
+        for _ in 0..player.required_prediction_count() {
+            let mut predicted_step = StepMap::new();
+            predicted_step
+                .insert(ParticipantId(0), SampleStep::MoveLeft(-1))
+                .expect("should insert map");
+            player.push_predicted_step(predicted_tick_id, &predicted_step)?;
+            predicted_tick_id += 1;
+        }
+
+        if player.can_join_player() && player.local_players().is_empty() {
+            player.request_join_player(&[0 as LocalIndex])?;
+        }
+
+        host.tick(now).expect("host should tick");
+        exchange(&mut host, &state_provider, player_connection_id, &mut player, now)?;
+        exchange(
+            &mut host,
+            &state_provider,
+            spectator_connection_id,
+            &mut spectator,
+            now,
+        )?;
+        now += MillisDuration::from_millis(16);
+    }
+
+    assert!(matches!(
+        spectator.request_join_player(&[0 as LocalIndex]),
+        Err(ClientError::SpectatorCanNotJoin)
+    ));
+    assert!(spectator.local_players().is_empty());
+    assert_eq!(spectator.required_prediction_count(), 0);
+    assert_eq!(host.session().participants.len(), 1);
+
+    assert!(spectator.debug_rectify().waiting_for_authoritative_tick_id() > TickId(0));
+    let spectator_game = spectator.game().expect("game state should be set");
+    assert_ne!(spectator_game.authoritative, initial_game_state);
+
+    Ok(())
+}
+
+struct MovingBot;
+
+impl Bot<SampleStep> for MovingBot {
+    fn step(&mut self, _: ParticipantId, _: TickId) -> SampleStep {
+        SampleStep::MoveRight(1)
+    }
+}
+
+#[test_log::test]
+fn late_joiner_follows_authoritative_steps_from_the_state_tick() -> Result<(), ClientError> {
+    let mut now = Millis::new(0);
+    let mut spectator = Client::<SampleGame, SampleStep>::new(now).with_spectator();
+
+    let mut host = Host::<SampleStep>::new(SampleGame::version(), TickId::new(20));
+    host.add_bot(DEFAULT_SESSION_ID, MovingBot)
+        .expect("should have room for a bot");
+    let spectator_connection_id = host.create_connection().expect("should work");
+
+    let initial_game_state = SampleGameState { x: -11, y: 42 };
+    let state_provider = TestStateProvider {
+        tick_id: TickId(20),
+        payload: initial_game_state.to_octets()?,
+    };
+
+    for _ in 0..60 {
+        host.tick(now).expect("host should tick");
+        exchange(
+            &mut host,
+            &state_provider,
+            spectator_connection_id,
+            &mut spectator,
+            now,
+        )?;
+        now += MillisDuration::from_millis(16);
+    }
+
+    assert!(spectator.debug_rectify().waiting_for_authoritative_tick_id() > TickId(21));
+    let spectator_game = spectator.game().expect("game state should be set");
+    assert_ne!(spectator_game.authoritative, initial_game_state);
+
+    Ok(())
+}
+
+fn push_predicted_steps(
+    player: &mut Client<SampleGame, SampleStep>,
+    predicted_tick_id: &mut TickId,
+) -> Result<(), ClientError> {
+    for _ in 0..player.required_prediction_count() {
+        let mut predicted_step = StepMap::new();
+        predicted_step
+            .insert(ParticipantId(0), SampleStep::MoveLeft(-1))
+            .expect("should insert map");
+        player.push_predicted_step(*predicted_tick_id, &predicted_step)?;
+        *predicted_tick_id += 1;
+    }
+    Ok(())
+}
+
+#[test_log::test]
+fn paused_session_predicts_nothing_until_the_resume_time() -> Result<(), ClientError> {
+    let mut now = Millis::new(0);
+    let mut player = Client::<SampleGame, SampleStep>::new(now);
+
+    let mut host = Host::<SampleStep>::new(SampleGame::version(), TickId::new(0));
+    let player_connection_id = host.create_connection().expect("should work");
+
+    let state_provider = TestStateProvider {
+        tick_id: TickId(0),
+        payload: SampleGameState { x: -11, y: 42 }.to_octets()?,
+    };
+
+    let mut predicted_tick_id = TickId(0);
+    for _ in 0..30 {
+        push_predicted_steps(&mut player, &mut predicted_tick_id)?;
+        if player.can_join_player() && player.local_players().is_empty() {
+            player.request_join_player(&[0 as LocalIndex])?;
+        }
+
+        host.tick(now).expect("host should tick");
+        exchange(&mut host, &state_provider, player_connection_id, &mut player, now)?;
+        now += MillisDuration::from_millis(16);
+    }
+    assert!(predicted_tick_id > TickId(0));
+
+    host.pause_session(DEFAULT_SESSION_ID)
+        .expect("default session should exist");
+    for _ in 0..10 {
+        push_predicted_steps(&mut player, &mut predicted_tick_id)?;
+        host.tick(now).expect("host should tick");
+        exchange(&mut host, &state_provider, player_connection_id, &mut player, now)?;
+        now += MillisDuration::from_millis(16);
+    }
+    assert!(player.session_paused_at().is_some());
+    assert_eq!(player.required_prediction_count(), 0);
+
+    let resume_at = now + MillisDuration::from_millis(100);
+    host.set_resume_delay(MillisDuration::from_millis(100));
+    host.resume_session(DEFAULT_SESSION_ID, now)
+        .expect("default session should exist");
+    while now < resume_at {
+        push_predicted_steps(&mut player, &mut predicted_tick_id)?;
+        host.tick(now).expect("host should tick");
+        exchange(&mut host, &state_provider, player_connection_id, &mut player, now)?;
+        assert_eq!(player.session_resume_at(), Some(resume_at));
+        assert_eq!(player.required_prediction_count(), 0);
+        now += MillisDuration::from_millis(16);
+    }
+
+    now = resume_at;
+    let paused_predicted_tick_id = predicted_tick_id;
+    for _ in 0..10 {
+        push_predicted_steps(&mut player, &mut predicted_tick_id)?;
+        host.tick(now).expect("host should tick");
+        exchange(&mut host, &state_provider, player_connection_id, &mut player, now)?;
+        now += MillisDuration::from_millis(16);
+    }
+    assert!(predicted_tick_id > paused_predicted_tick_id);
+    assert_eq!(player.session_paused_at(), None);
 
     Ok(())
 }
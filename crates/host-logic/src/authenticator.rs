@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use nimble_protocol::client_to_host::{ConnectRequest, JoinGameRequest};
+use nimble_protocol::host_to_client::{ConnectionRejectedReason, JoinGameRejectedReason};
+
+/// Opaque data that the [`Authenticator`] attaches to a participant, e.g. a user id from a backend.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParticipantMetadata(pub Vec<u8>);
+
+/// Decides which clients that are allowed to connect and join participants.
+///
+/// The client token is the opaque token from the [`ConnectRequest`], e.g. a ticket issued by a backend.
+pub trait Authenticator {
+    /// Called for connect requests that passed the version checks.
+    ///
+    /// # Errors
+    ///
+    /// The `ConnectionRejectedReason` that is sent back if the connection is not accepted.
+    fn authenticate_connect(
+        &mut self,
+        connect_request: &ConnectRequest,
+    ) -> Result<(), ConnectionRejectedReason>;
+
+    /// Called for every join game request, before the participant limits are checked.
+    ///
+    /// Returns the metadata for each of the requested players, in the same order as the player
+    /// requests. Players without metadata get the default metadata.
+    ///
+    /// # Errors
+    ///
+    /// The `JoinGameRejectedReason` that is sent back if the join is not accepted.
+    fn authenticate_join(
+        &mut self,
+        client_token: &[u8],
+        join_request: &JoinGameRequest,
+    ) -> Result<Vec<ParticipantMetadata>, JoinGameRejectedReason>;
+}
+
+/// Accepts every client. Used until another [`Authenticator`] is set.
+#[derive(Debug, Default)]
+pub struct AcceptAllAuthenticator;
+
+impl Authenticator for AcceptAllAuthenticator {
+    fn authenticate_connect(
+        &mut self,
+        _connect_request: &ConnectRequest,
+    ) -> Result<(), ConnectionRejectedReason> {
+        Ok(())
+    }
+
+    fn authenticate_join(
+        &mut self,
+        _client_token: &[u8],
+        _join_request: &JoinGameRequest,
+    ) -> Result<Vec<ParticipantMetadata>, JoinGameRejectedReason> {
+        Ok(vec![])
+    }
+}
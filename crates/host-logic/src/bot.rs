@@ -0,0 +1,13 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use nimble_participant::ParticipantId;
+use tick_id::TickId;
+
+/// Provides the steps for a server side participant that has no connection, e.g. an AI player
+/// that backfills a match.
+pub trait Bot<T> {
+    /// Called once for every tick that is produced, before the tick is combined.
+    fn step(&mut self, participant_id: ParticipantId, tick_id: TickId) -> T;
+}
@@ -2,13 +2,16 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+use crate::bot::Bot;
+use crate::missing_step::{ForcedStepPolicy, MissingStepPolicy};
 use err_rs::{ErrorLevel, ErrorLevelProvider};
 use log::trace;
-use nimble_participant::ParticipantId;
+use monotonic_time_rs::MillisDuration;
+use nimble_participant::{ParticipantId, HOST_PARTICIPANT_ID};
 use nimble_step::Step;
 use nimble_step_map::StepMap;
 use seq_map::SeqMapError;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tick_id::TickId;
 use tick_queue::{Queue, QueueError};
 
@@ -23,6 +26,8 @@ pub enum CombinatorError {
     SeqMapError(SeqMapError),
     NoBufferForParticipant,
     QueueError(QueueError),
+    HostStepTickAlreadyProduced(TickId),
+    HostStepAlreadyExists(TickId),
 }
 
 impl From<QueueError> for CombinatorError {
@@ -38,6 +43,9 @@ impl ErrorLevelProvider for CombinatorError {
             | Self::OtherError
             | Self::SeqMapError(_)
             | Self::NoBufferForParticipant => ErrorLevel::Info,
+            Self::HostStepTickAlreadyProduced(_) | Self::HostStepAlreadyExists(_) => {
+                ErrorLevel::Warning
+            }
             Self::QueueError(_) => ErrorLevel::Critical,
         }
     }
@@ -49,10 +57,28 @@ impl From<SeqMapError> for CombinatorError {
     }
 }
 
-#[derive(Default)]
 pub struct Combinator<T: Clone> {
     pub in_buffers: HashMap<ParticipantId, Queue<T>>,
     pub tick_id_to_produce: TickId,
+    /// Steps decided by the host (e.g. `Step::Left`) that are inserted into the next produced step.
+    pub announcements: HashMap<ParticipantId, Step<T>>,
+    /// Participants that have lost their connection, they get `Step::WaitingForReconnect` every tick.
+    pub waiting_for_reconnect: HashSet<ParticipantId>,
+    /// Participants that get `Step::Forced` produced, regardless of the steps they send.
+    pub forced_participants: HashSet<ParticipantId>,
+    /// The last `Step::Custom` produced for each participant, used by the missing step policy.
+    pub last_custom_steps: HashMap<ParticipantId, T>,
+    /// Steps from the host itself, produced for [`HOST_PARTICIPANT_ID`] on their tick.
+    pub host_steps: BTreeMap<TickId, T>,
+    /// Participants without a connection that get their steps from a [`Bot`].
+    bots: HashMap<ParticipantId, Box<dyn Bot<T>>>,
+    missing_step_policy: Box<dyn MissingStepPolicy<T>>,
+}
+
+impl<T: Clone + std::fmt::Display> Default for Combinator<T> {
+    fn default() -> Self {
+        Self::new(TickId(0))
+    }
 }
 
 impl<T: Clone + std::fmt::Display> Combinator<T> {
@@ -61,11 +87,113 @@ impl<T: Clone + std::fmt::Display> Combinator<T> {
         Self {
             in_buffers: HashMap::new(),
             tick_id_to_produce,
+            announcements: HashMap::new(),
+            waiting_for_reconnect: HashSet::new(),
+            forced_participants: HashSet::new(),
+            last_custom_steps: HashMap::new(),
+            host_steps: BTreeMap::new(),
+            bots: HashMap::new(),
+            missing_step_policy: Box::new(ForcedStepPolicy),
         }
     }
 
+    /// Sets the policy that decides the steps for participants that have not provided a step.
+    /// Until it is set, `Step::Forced` is produced for them.
+    pub fn set_missing_step_policy(&mut self, policy: impl MissingStepPolicy<T> + 'static) {
+        self.missing_step_policy = Box::new(policy);
+    }
+
+    /// How long the missing step policy waits for every participant past the deadline of a tick,
+    /// or `None` if it does not wait.
+    #[must_use]
+    pub fn lockstep_wait(&self) -> Option<MillisDuration> {
+        self.missing_step_policy.lockstep_wait()
+    }
+
     pub fn create_buffer(&mut self, id: ParticipantId) {
-        self.in_buffers.insert(id, Queue::default());
+        self.in_buffers
+            .insert(id, Queue::new(self.tick_id_to_produce));
+    }
+
+    pub fn remove_buffer(&mut self, id: ParticipantId) -> Option<Queue<T>> {
+        self.waiting_for_reconnect.remove(&id);
+        self.forced_participants.remove(&id);
+        self.last_custom_steps.remove(&id);
+        self.bots.remove(&id);
+        self.in_buffers.remove(&id)
+    }
+
+    /// Removes the buffer for the participant and produces `Step::WaitingForReconnect`
+    /// for it until [`Combinator::reconnect`] or [`Combinator::remove_buffer`] is called.
+    pub fn wait_for_reconnect(&mut self, id: ParticipantId) {
+        self.in_buffers.remove(&id);
+        self.waiting_for_reconnect.insert(id);
+    }
+
+    /// Creates a new buffer for a participant that was waiting for reconnect.
+    ///
+    /// Returns `false` if the participant was not waiting for reconnect.
+    pub fn reconnect(&mut self, id: ParticipantId) -> bool {
+        if !self.waiting_for_reconnect.remove(&id) {
+            return false;
+        }
+        if !self.forced_participants.contains(&id) {
+            self.create_buffer(id);
+        }
+        true
+    }
+
+    /// Removes the buffer for the participant and produces `Step::Forced` for it
+    /// until [`Combinator::remove_buffer`] is called.
+    pub fn force_steps(&mut self, id: ParticipantId) {
+        self.in_buffers.remove(&id);
+        self.forced_participants.insert(id);
+    }
+
+    /// Lets the bot provide the steps for the participant, every produced tick.
+    pub fn add_bot(&mut self, id: ParticipantId, bot: Box<dyn Bot<T>>) {
+        self.bots.insert(id, bot);
+    }
+
+    /// Stops asking the bot for steps and creates a buffer for the participant instead,
+    /// so a player can take over the participant.
+    ///
+    /// Returns `false` if the participant was not a bot.
+    pub fn replace_bot(&mut self, id: ParticipantId) -> bool {
+        if self.bots.remove(&id).is_none() {
+            return false;
+        }
+        self.create_buffer(id);
+        true
+    }
+
+    #[must_use]
+    pub fn is_bot(&self, id: ParticipantId) -> bool {
+        self.bots.contains_key(&id)
+    }
+
+    /// Announces a step for the participant that overrides whatever the participant
+    /// provides for the next produced tick. A later announcement replaces an earlier one.
+    pub fn announce(&mut self, id: ParticipantId, step: Step<T>) {
+        self.announcements.insert(id, step);
+    }
+
+    /// Adds a custom step from the host that is produced for [`HOST_PARTICIPANT_ID`]
+    /// when `tick_id` is produced.
+    ///
+    /// # Errors
+    ///
+    /// `CombinatorError::HostStepTickAlreadyProduced` if the tick has already been produced, or
+    /// `CombinatorError::HostStepAlreadyExists` if there is already a host step for the tick.
+    pub fn push_host_step(&mut self, tick_id: TickId, step: T) -> Result<(), CombinatorError> {
+        if tick_id < self.tick_id_to_produce {
+            return Err(CombinatorError::HostStepTickAlreadyProduced(tick_id));
+        }
+        if self.host_steps.contains_key(&tick_id) {
+            return Err(CombinatorError::HostStepAlreadyExists(tick_id));
+        }
+        self.host_steps.insert(tick_id, step);
+        Ok(())
     }
 
     /// # Errors
@@ -114,10 +242,10 @@ impl<T: Clone + std::fmt::Display> Combinator<T> {
     /// # Errors
     ///
     /// `CombinatorError` // TODO:
-    #[allow(clippy::missing_panics_doc)]
     pub fn produce(&mut self) -> Result<(TickId, StepMap<Step<T>>), CombinatorError> {
         let (can_provide, can_not_provide) = self.participants_that_can_provide();
-        if can_provide == 0 {
+        let waits_for_everyone = self.lockstep_wait().is_some();
+        if can_provide == 0 || (waits_for_everyone && can_not_provide > 0) {
             trace!(
                 "notice: can not produce authoritative step {}, no one can provide it",
                 self.tick_id_to_produce
@@ -133,6 +261,30 @@ impl<T: Clone + std::fmt::Display> Combinator<T> {
             can_not_provide
         );
 
+        self.combine(false)
+    }
+
+    /// Produces the step for `tick_id_to_produce`, even if no participant can provide it.
+    ///
+    /// Every participant that has not provided a step for the tick gets the step from the
+    /// missing step policy, `Step::Forced` by default.
+    ///
+    /// # Errors
+    ///
+    /// `CombinatorError` // TODO:
+    pub fn produce_forced(&mut self) -> Result<(TickId, StepMap<Step<T>>), CombinatorError> {
+        trace!(
+            "forced to produce authoritative step {}",
+            self.tick_id_to_produce
+        );
+        self.combine(true)
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    fn combine(
+        &mut self,
+        force_missing_steps: bool,
+    ) -> Result<(TickId, StepMap<Step<T>>), CombinatorError> {
         let mut combined_step = StepMap::<Step<T>>::new();
         for (participant_id, steps) in &mut self.in_buffers {
             if let Some(first_tick) = steps.front_tick_id() {
@@ -147,16 +299,63 @@ impl<T: Clone + std::fmt::Display> Combinator<T> {
                         .insert(*participant_id, Step::Custom(steps.pop().unwrap().item))?;
                 } else {
                     trace!(
-                        "did not find step from {} for {}, setting it to forced",
+                        "did not find step from {} for {}, using the missing step policy",
                         first_tick,
                         participant_id
                     );
-                    combined_step.insert(*participant_id, Step::Forced)?;
+                    let missing_step = self
+                        .missing_step_policy
+                        .missing_step(*participant_id, self.last_custom_steps.get(participant_id));
+                    combined_step.insert(*participant_id, missing_step)?;
                     steps.discard_up_to(self.tick_id_to_produce);
                 }
+            } else if force_missing_steps {
+                trace!(
+                    "no steps from {} for {}, using the missing step policy",
+                    participant_id,
+                    self.tick_id_to_produce
+                );
+                let missing_step = self
+                    .missing_step_policy
+                    .missing_step(*participant_id, self.last_custom_steps.get(participant_id));
+                combined_step.insert(*participant_id, missing_step)?;
             }
         }
 
+        for (participant_id, bot) in &mut self.bots {
+            let bot_step = bot.step(*participant_id, self.tick_id_to_produce);
+            combined_step.insert(*participant_id, Step::Custom(bot_step))?;
+        }
+
+        for participant_id in &self.waiting_for_reconnect {
+            combined_step.insert(*participant_id, Step::WaitingForReconnect)?;
+        }
+
+        for participant_id in &self.forced_participants {
+            if combined_step.get(participant_id).is_none() {
+                combined_step.insert(*participant_id, Step::Forced)?;
+            }
+        }
+
+        for (participant_id, announced_step) in self.announcements.drain() {
+            if let Some(existing_step) = combined_step.get_mut(&participant_id) {
+                *existing_step = announced_step;
+            } else {
+                combined_step.insert(participant_id, announced_step)?;
+            }
+        }
+
+        for (participant_id, step) in &combined_step {
+            if let Step::Custom(custom_step) = step {
+                self.last_custom_steps
+                    .insert(*participant_id, custom_step.clone());
+            }
+        }
+
+        if let Some(host_step) = self.host_steps.remove(&self.tick_id_to_produce) {
+            combined_step.insert(HOST_PARTICIPANT_ID, Step::Custom(host_step))?;
+        }
+
         self.tick_id_to_produce += 1;
 
         Ok((self.tick_id_to_produce - 1, combined_step))
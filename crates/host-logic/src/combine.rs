@@ -2,11 +2,14 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
-use crate::combinator::Combinator;
+use crate::bot::Bot;
+use crate::combinator::{Combinator, CombinatorError};
+use crate::missing_step::MissingStepPolicy;
 use err_rs::{ErrorLevel, ErrorLevelProvider};
+use monotonic_time_rs::MillisDuration;
 use nimble_participant::ParticipantId;
 
-use nimble_step::Step;
+use nimble_step::{JoinedData, Step};
 use nimble_step_map::StepMap;
 use tick_id::TickId;
 use tick_queue::{Queue, QueueError};
@@ -15,6 +18,13 @@ use tick_queue::{Queue, QueueError};
 pub enum HostCombinatorError {
     NoBufferForParticipant,
     QueueError(QueueError),
+    CombinatorError(CombinatorError),
+}
+
+impl From<CombinatorError> for HostCombinatorError {
+    fn from(error: CombinatorError) -> Self {
+        Self::CombinatorError(error)
+    }
 }
 
 impl From<QueueError> for HostCombinatorError {
@@ -28,22 +38,27 @@ impl ErrorLevelProvider for HostCombinatorError {
         match self {
             Self::NoBufferForParticipant => ErrorLevel::Warning,
             Self::QueueError(_) => ErrorLevel::Critical,
+            Self::CombinatorError(err) => err.error_level(),
         }
     }
 }
 
-#[allow(unused)]
 pub struct HostCombinator<T: Clone + std::fmt::Display> {
     combinator: Combinator<T>,
     authoritative_steps: Queue<StepMap<Step<T>>>,
+    paused: bool,
+    /// The participants that got `Step::Forced` produced, since the last
+    /// [`HostCombinator::take_forced_steps`].
+    forced_steps: Vec<(TickId, ParticipantId)>,
 }
 
-#[allow(unused)]
 impl<T: Clone + std::fmt::Display> HostCombinator<T> {
     pub fn new(tick_id: TickId) -> Self {
         Self {
             combinator: Combinator::<T>::new(tick_id),
-            authoritative_steps: Queue::default(),
+            authoritative_steps: Queue::new(tick_id),
+            paused: false,
+            forced_steps: vec![],
         }
     }
 
@@ -51,26 +66,176 @@ impl<T: Clone + std::fmt::Display> HostCombinator<T> {
         self.combinator.tick_id_to_produce
     }
 
+    /// Stops producing authoritative steps until [`HostCombinator::resume`] is called.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continues producing authoritative steps from the tick where it was paused.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns the tick that the next authoritative step will be produced for, if paused.
+    pub const fn paused_at(&self) -> Option<TickId> {
+        if self.paused {
+            Some(self.combinator.tick_id_to_produce)
+        } else {
+            None
+        }
+    }
+
+    /// Creates a buffer for the participant and announces a `Step::Joined` for it
+    /// in the next produced authoritative step, so all clients can spawn the participant
+    /// on the same tick.
     pub fn create_buffer(&mut self, participant_id: ParticipantId) {
         self.combinator.create_buffer(participant_id);
+        self.combinator.announce(
+            participant_id,
+            Step::Joined(JoinedData {
+                tick_id: self.combinator.tick_id_to_produce,
+            }),
+        );
+    }
+
+    /// Lets the bot provide the steps for the participant and announces a `Step::Joined` for it
+    /// in the next produced authoritative step.
+    pub fn add_bot(&mut self, participant_id: ParticipantId, bot: Box<dyn Bot<T>>) {
+        self.combinator.add_bot(participant_id, bot);
+        self.combinator.announce(
+            participant_id,
+            Step::Joined(JoinedData {
+                tick_id: self.combinator.tick_id_to_produce,
+            }),
+        );
+    }
+
+    /// Expects steps from a player for a participant that was a bot. No step is announced,
+    /// the participant continues on the same timeline.
+    pub fn replace_bot(&mut self, participant_id: ParticipantId) -> bool {
+        self.combinator.replace_bot(participant_id)
+    }
+
+    #[must_use]
+    pub fn is_bot(&self, participant_id: ParticipantId) -> bool {
+        self.combinator.is_bot(participant_id)
+    }
+
+    /// Removes the buffer for the participant and announces a `Step::Left` for it
+    /// in the next produced authoritative step.
+    pub fn remove_buffer(&mut self, participant_id: ParticipantId) {
+        self.combinator.remove_buffer(participant_id);
+        self.combinator.announce(participant_id, Step::Left);
+    }
+
+    /// Stops expecting steps from the participant and produces `Step::WaitingForReconnect`
+    /// for it until it is reconnected or removed.
+    pub fn wait_for_reconnect(&mut self, participant_id: ParticipantId) {
+        self.combinator.wait_for_reconnect(participant_id);
+    }
+
+    /// Starts expecting steps from a participant that was waiting for reconnect.
+    pub fn reconnect(&mut self, participant_id: ParticipantId) -> bool {
+        self.combinator.reconnect(participant_id)
+    }
+
+    /// Produces `Step::Forced` for the participant from now on, until it is removed.
+    pub fn force_steps(&mut self, participant_id: ParticipantId) {
+        self.combinator.force_steps(participant_id);
+    }
+
+    pub fn set_missing_step_policy(&mut self, policy: impl MissingStepPolicy<T> + 'static) {
+        self.combinator.set_missing_step_policy(policy);
+    }
+
+    pub fn lockstep_wait(&self) -> Option<MillisDuration> {
+        self.combinator.lockstep_wait()
+    }
+
+    /// Adds a custom step from the host for `tick_id`, see [`Combinator::push_host_step`].
+    pub fn push_host_step(&mut self, tick_id: TickId, step: T) -> Result<(), CombinatorError> {
+        self.combinator.push_host_step(tick_id, step)
+    }
+
+    pub fn get(&self, participant_id: ParticipantId) -> Option<&Queue<T>> {
+        self.combinator.in_buffers.get(&participant_id)
     }
 
     pub fn get_mut(&mut self, participant_id: ParticipantId) -> Option<&mut Queue<T>> {
         self.combinator.in_buffers.get_mut(&participant_id)
     }
 
+    /// Returns the oldest tick that the authoritative step is still kept for, or the tick
+    /// to produce if no authoritative steps are kept.
+    pub fn oldest_authoritative_tick_id(&self) -> TickId {
+        self.authoritative_steps
+            .front_tick_id()
+            .unwrap_or(self.combinator.tick_id_to_produce)
+    }
+
     pub const fn authoritative_steps(&self) -> &Queue<StepMap<Step<T>>> {
         &self.authoritative_steps
     }
 
-    pub fn produce_authoritative_steps(&mut self) {
+    /// Removes all authoritative steps before `tick_id`.
+    pub fn discard_authoritative_steps_up_to(&mut self, tick_id: TickId) {
+        self.authoritative_steps.discard_up_to(tick_id);
+    }
+
+    /// Returns the participants that got `Step::Forced` produced since the last call.
+    pub fn take_forced_steps(&mut self) -> Vec<(TickId, ParticipantId)> {
+        std::mem::take(&mut self.forced_steps)
+    }
+
+    /// Produces authoritative steps for every tick up to, but not including, `deadline_tick_id`,
+    /// even if the participants have not provided any steps. The missing steps are decided by the
+    /// missing step policy. Nothing is produced while paused.
+    ///
+    /// # Errors
+    ///
+    /// `HostCombinatorError` if the produced step could not be added.
+    pub fn produce_authoritative_steps_until(
+        &mut self,
+        deadline_tick_id: TickId,
+    ) -> Result<(), HostCombinatorError> {
+        while !self.paused && self.combinator.tick_id_to_produce < deadline_tick_id {
+            let (produced_tick_id, new_combined_step) = self.combinator.produce_forced()?;
+            self.push_authoritative_step(produced_tick_id, new_combined_step)?;
+        }
+        Ok(())
+    }
+
+    /// Produces the authoritative steps that every participant has provided steps for.
+    /// Nothing is produced while paused.
+    ///
+    /// # Errors
+    ///
+    /// `HostCombinatorError` if the produced step could not be added.
+    pub fn produce_authoritative_steps(&mut self) -> Result<(), HostCombinatorError> {
+        if self.paused {
+            return Ok(());
+        }
         for _ in 0..10 {
             if let Ok((produced_tick_id, new_combined_step)) = self.combinator.produce() {
-                self.authoritative_steps
-                    .push(produced_tick_id, new_combined_step);
+                self.push_authoritative_step(produced_tick_id, new_combined_step)?;
             } else {
                 break;
             }
         }
+        Ok(())
+    }
+
+    /// Adds the produced step and remembers the participants that got `Step::Forced`.
+    fn push_authoritative_step(
+        &mut self,
+        tick_id: TickId,
+        combined_step: StepMap<Step<T>>,
+    ) -> Result<(), QueueError> {
+        for (participant_id, step) in &combined_step {
+            if matches!(step, Step::Forced) {
+                self.forced_steps.push((tick_id, *participant_id));
+            }
+        }
+        self.authoritative_steps.push(tick_id, combined_step)
     }
 }
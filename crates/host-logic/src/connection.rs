@@ -3,34 +3,48 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 
+use crate::authenticator::{Authenticator, ParticipantMetadata};
 use crate::combine::HostCombinator;
-use crate::session::Participant;
-use crate::{GameSession, GameStateProvider, HostLogicError, Phase};
+use crate::event::ConnectionEvent;
+use crate::rate_limit::{ConnectionRateLimiter, RateLimitViolation, RateLimits};
+use crate::session::{Participant, Party};
+use crate::validator::{StepValidation, StepValidator, StepViolationAction, StepViolationSettings};
+use crate::{GameSession, HostLogicError, Phase, SessionGameStateProvider, DEFAULT_SESSION_ID};
 use app_version::Version;
+use flood_rs::prelude::OutOctetStream;
 use flood_rs::{Deserialize, Serialize};
 use log::{debug, trace};
 use monotonic_time_rs::Millis;
 use nimble_blob_stream::out_logic_front::OutLogicFront;
-use nimble_blob_stream::prelude::{ReceiverToSenderFrontCommands, TransferId};
+use nimble_blob_stream::prelude::{
+    BlobStreamOutSettings, ReceiverToSenderFrontCommands, TransferId,
+};
 use nimble_participant::ParticipantId;
 use nimble_protocol::client_to_host::{
-    ConnectRequest, DownloadGameStateRequest, JoinGameRequest, StepsRequest,
+    ConnectRequest, DownloadGameStateRequest, JoinGameRequest, JoinGameType, LeaveGameRequest,
+    SessionPauseRequest, StepsRequest,
 };
 use nimble_protocol::host_to_client::{
-    AuthoritativeStepRanges, ConnectionAccepted, DownloadGameStateResponse, GameStepResponse,
-    GameStepResponseHeader, HostToClientCommands, JoinGameAccepted, JoinGameParticipant,
-    JoinGameParticipants, PartyAndSessionSecret,
+    AuthoritativeStepRanges, ConnectionAccepted, ConnectionRejected, ConnectionRejectedReason,
+    DownloadGameStateResponse, GameStepResponse, GameStepResponseHeader, HostToClientCommands,
+    JoinGameAccepted, JoinGameParticipant, JoinGameParticipants, JoinGameRejected,
+    JoinGameRejectedReason, LeaveGameAccepted, PartyAndSessionSecret, PongInfo,
 };
 use nimble_protocol::prelude::CombinedSteps;
-use nimble_protocol::SessionConnectionSecret;
+use nimble_protocol::{SessionId, NIMBLE_PROTOCOL_VERSION};
 use nimble_step::Step;
+use nimble_step_map::StepMap;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::time::Duration;
 use tick_id::TickId;
+use tick_queue::Queue;
+
+/// The maximum number of octets of authoritative steps in a single game step response,
+/// so the response fits in a datagram.
+const AUTHORITATIVE_STEPS_OCTET_BUDGET: usize = 900;
 
 #[derive(Debug)]
 #[allow(clippy::new_without_default)]
@@ -38,8 +52,23 @@ pub struct Connection<StepT: Clone + Eq + Debug + Deserialize + Serialize> {
     pub participant_lookup: HashMap<ParticipantId, Rc<RefCell<Participant>>>,
     pub out_blob_stream: Option<OutLogicFront>,
     pub blob_stream_for_client_request: Option<u8>,
+    /// The party that the participants of this connection belong to.
+    pub party_id: Option<u8>,
+    /// Banned connections get every connect request rejected.
+    pub banned: bool,
+    /// The client token from the accepted connect request.
+    pub client_token: Vec<u8>,
+    /// The game session that the connection has connected to.
+    pub session_id: SessionId,
     last_transfer_id: u16,
     pub(crate) phase: Phase,
+    pub(crate) rate_limiter: ConnectionRateLimiter,
+    pub(crate) events: Vec<ConnectionEvent>,
+    waiting_for_tick_id: Option<TickId>,
+    /// Set once the host sends to the connection on its own schedule, see [`Connection::send`].
+    is_sent_to_on_schedule: bool,
+    /// The time and lower millis of the latest ping that has not been answered yet.
+    pending_ping: Option<(Millis, u16)>,
     #[allow(unused)]
     debug_counter: u16,
     phantom_data: PhantomData<StepT>,
@@ -53,8 +82,17 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
             participant_lookup: HashMap::default(),
             out_blob_stream: None,
             blob_stream_for_client_request: None,
+            party_id: None,
+            banned: false,
+            client_token: Vec::new(),
+            session_id: DEFAULT_SESSION_ID,
             last_transfer_id: 0,
+            waiting_for_tick_id: None,
+            is_sent_to_on_schedule: false,
+            pending_ping: None,
             debug_counter: 0,
+            rate_limiter: ConnectionRateLimiter::default(),
+            events: vec![],
             phase: Phase::WaitingForValidConnectRequest,
             phantom_data: PhantomData,
         }
@@ -65,6 +103,10 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         &self.phase
     }
 
+    /// Accepts the connect request, or responds with a `ConnectionRejected` if the connection
+    /// is banned, the versions do not match, the requested session can not be connected to,
+    /// as told by `session_rejected_reason`, the host is full or the `authenticator` rejects it.
+    ///
     /// # Errors
     ///
     /// `HostLogicError` // TODO:
@@ -72,19 +114,47 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         &mut self,
         connect_request: &ConnectRequest,
         required_deterministic_simulation_version: &Version,
+        session_rejected_reason: Option<ConnectionRejectedReason>,
+        is_session_full: bool,
+        authenticator: &mut dyn Authenticator,
     ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
-        self.phase = Phase::Connected;
-
         let connect_version = Version::new(
             connect_request.application_version.major,
             connect_request.application_version.minor,
             connect_request.application_version.patch,
         );
 
-        if connect_version != *required_deterministic_simulation_version {
-            return Err(HostLogicError::WrongApplicationVersion);
+        let rejected_reason = if self.banned {
+            Some(ConnectionRejectedReason::Banned)
+        } else if connect_request.nimble_version != NIMBLE_PROTOCOL_VERSION {
+            Some(ConnectionRejectedReason::NimbleVersionMismatch)
+        } else if connect_version != *required_deterministic_simulation_version {
+            Some(ConnectionRejectedReason::ApplicationVersionMismatch)
+        } else if session_rejected_reason.is_some() {
+            session_rejected_reason
+        } else if is_session_full {
+            Some(ConnectionRejectedReason::SessionFull)
+        } else {
+            authenticator.authenticate_connect(connect_request).err()
+        };
+
+        if let Some(reason) = rejected_reason {
+            let response = ConnectionRejected {
+                response_to_request: connect_request.client_request_id,
+                reason,
+            };
+            debug!(
+                "host-stream received connect request {:?} and rejected it:\n{:?}",
+                connect_request, response
+            );
+            self.phase = Phase::WaitingForValidConnectRequest;
+            return Ok([HostToClientCommands::ConnectionRejected(response)].into());
         }
 
+        self.phase = Phase::Connected;
+        self.client_token.clone_from(&connect_request.client_token);
+        self.session_id = connect_request.session_id;
+
         let response = ConnectionAccepted {
             flags: 0,
             response_to_request: connect_request.client_request_id,
@@ -96,11 +166,37 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         Ok([HostToClientCommands::ConnectType(response)].into())
     }
 
+    /// Detaches the connection from its game session, so it has to connect again.
+    ///
+    /// The party is released in the `session`, or just forgotten if the session has been removed.
+    pub(crate) fn leave_session(
+        &mut self,
+        session: Option<&mut GameSession<StepT>>,
+    ) -> Result<(), HostLogicError> {
+        self.phase = Phase::WaitingForValidConnectRequest;
+        if let Some(session) = session {
+            self.release_party(session)?;
+        }
+        self.party_id = None;
+        self.participant_lookup.clear();
+        self.out_blob_stream = None;
+        self.blob_stream_for_client_request = None;
+        self.waiting_for_tick_id = None;
+        Ok(())
+    }
+
+    /// The first authoritative tick that the remote has not received yet, as acknowledged
+    /// in the last steps request, or the tick of the game state that the remote downloads.
+    #[must_use]
+    pub const fn waiting_for_tick_id(&self) -> Option<TickId> {
+        self.waiting_for_tick_id
+    }
+
     #[must_use]
     pub fn is_state_received_by_remote(&self) -> bool {
         self.out_blob_stream
             .as_ref()
-            .map_or(false, OutLogicFront::is_received_by_remote)
+            .is_some_and(OutLogicFront::is_received_by_remote)
     }
 
     pub(crate) fn on_blob_stream(
@@ -112,7 +208,11 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
             .out_blob_stream
             .as_mut()
             .ok_or(HostLogicError::NoDownloadNow)?;
-        blob_stream.receive(blob_stream_command)?;
+        let was_received_by_remote = blob_stream.is_received_by_remote();
+        blob_stream.receive(now, blob_stream_command)?;
+        if !was_received_by_remote && blob_stream.is_received_by_remote() {
+            self.events.push(ConnectionEvent::DownloadCompleted);
+        }
         let blob_commands = blob_stream.send(now)?;
 
         let converted_commands: Vec<_> = blob_commands
@@ -123,34 +223,72 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         Ok(converted_commands)
     }
 
+    /// Joins the requested participants, or responds with a `JoinGameRejected` if the
+    /// `authenticator` rejects the request, the request exceeds the participant limits of the
+    /// session or the session secret is unknown.
     pub(crate) fn on_join(
         &mut self,
         session: &mut GameSession<StepT>,
+        authenticator: &mut dyn Authenticator,
         request: &JoinGameRequest,
     ) -> Result<HostToClientCommands<Step<StepT>>, HostLogicError> {
         debug!("on_join {:?}", request);
 
-        if request.player_requests.players.is_empty() {
-            return Err(HostLogicError::NoFreeParticipantIds);
-        }
-
-        let local_indices: Vec<_> = request
-            .player_requests
-            .players
-            .iter()
-            .map(|p| p.local_index)
-            .collect();
+        let metadata = match authenticator.authenticate_join(&self.client_token, request) {
+            Ok(metadata) => metadata,
+            Err(reason) => return Ok(Self::join_rejected(request, reason)),
+        };
 
-        let participants = session
-            .create_participants(local_indices.as_slice())
-            .ok_or(HostLogicError::NoFreeParticipantIds)?;
+        let participants = match request.join_game_type {
+            // TODO: Host migration is not supported yet, so it is handled as a new join
+            JoinGameType::NoSecret | JoinGameType::HostMigrationParticipantId(_) => {
+                if let Some(reason) =
+                    self.check_participant_limits(session, request.player_requests.players.len())
+                {
+                    return Ok(Self::join_rejected(request, reason));
+                }
+                match self.join_new_participants(session, request, metadata)? {
+                    Some(participants) => participants,
+                    None => {
+                        return Ok(Self::join_rejected(
+                            request,
+                            JoinGameRejectedReason::SessionFull,
+                        ))
+                    }
+                }
+            }
+            JoinGameType::UseSessionSecret(secret) => {
+                let Some(party) = session.find_party_by_secret(secret) else {
+                    return Ok(Self::join_rejected(
+                        request,
+                        JoinGameRejectedReason::UnknownSessionSecret,
+                    ));
+                };
+                if !Self::is_same_party_metadata(session, party, request, &metadata) {
+                    return Ok(Self::join_rejected(
+                        request,
+                        JoinGameRejectedReason::Unauthorized,
+                    ));
+                }
+                let party_id = party.id;
+                if self.party_id != Some(party_id) {
+                    self.release_party(session)?;
+                }
+                self.party_id = Some(party_id);
+                session.reconnect_party(party_id)?
+            }
+        };
 
         for participant in &participants {
             self.participant_lookup
                 .insert(participant.borrow().id, participant.clone());
-            session.combinator.create_buffer(participant.borrow().id);
         }
 
+        let party = self
+            .party_id
+            .and_then(|party_id| session.parties.get(&party_id))
+            .expect("the connection has joined a party above");
+
         let join_game_participants = participants
             .iter()
             .map(|found_participant| JoinGameParticipant {
@@ -162,8 +300,8 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         let join_accepted = JoinGameAccepted {
             client_request_id: request.client_request_id,
             party_and_session_secret: PartyAndSessionSecret {
-                session_secret: SessionConnectionSecret { value: 0 },
-                party_id: 0,
+                session_secret: party.secret,
+                party_id: party.id,
             },
             participants: JoinGameParticipants(join_game_participants),
         };
@@ -171,31 +309,281 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         Ok(HostToClientCommands::JoinGame(join_accepted))
     }
 
+    /// Checks that the `authenticator` gave the players in the request the same metadata as
+    /// the participants of the party got when they joined, so a leaked secret is not enough
+    /// to take over the participants of another user.
+    fn is_same_party_metadata(
+        session: &GameSession<StepT>,
+        party: &Party,
+        request: &JoinGameRequest,
+        metadata: &[ParticipantMetadata],
+    ) -> bool {
+        party
+            .participant_ids
+            .iter()
+            .filter_map(|participant_id| session.participants.get(participant_id))
+            .all(|participant| {
+                let participant = participant.borrow();
+                let requested_metadata = request
+                    .player_requests
+                    .players
+                    .iter()
+                    .position(|player| player.local_index == participant.client_local_index)
+                    .and_then(|index| metadata.get(index))
+                    .cloned()
+                    .unwrap_or_default();
+                participant.metadata == requested_metadata
+            })
+    }
+
+    /// Lets go of the party of the connection. A party with participants waits for a reconnect
+    /// with its secret until the reconnect timeout, and an empty party is removed.
+    pub(crate) fn release_party(
+        &mut self,
+        session: &mut GameSession<StepT>,
+    ) -> Result<(), HostLogicError> {
+        self.participant_lookup.clear();
+        let Some(party_id) = self.party_id.take() else {
+            return Ok(());
+        };
+        let Some(party) = session.parties.get(&party_id) else {
+            return Ok(());
+        };
+        if party.participant_ids.is_empty() {
+            session.remove_party(party_id)
+        } else {
+            session.disconnect_party(party_id)
+        }
+    }
+
+    fn check_participant_limits(
+        &self,
+        session: &GameSession<StepT>,
+        local_player_count: usize,
+    ) -> Option<JoinGameRejectedReason> {
+        let participant_limits = &session.participant_limits;
+        if local_player_count == 0 {
+            Some(JoinGameRejectedReason::NoLocalPlayers)
+        } else if local_player_count > participant_limits.max_local_players_per_request {
+            Some(JoinGameRejectedReason::TooManyLocalPlayers)
+        } else if self.participant_lookup.len() + local_player_count
+            > participant_limits.max_participants_per_connection
+        {
+            Some(JoinGameRejectedReason::TooManyParticipantsForConnection)
+        } else if session.participants.len() + local_player_count
+            > participant_limits.max_participants + session.bot_count()
+        {
+            Some(JoinGameRejectedReason::SessionFull)
+        } else {
+            None
+        }
+    }
+
+    fn join_rejected(
+        request: &JoinGameRequest,
+        reason: JoinGameRejectedReason,
+    ) -> HostToClientCommands<Step<StepT>> {
+        let response = JoinGameRejected {
+            client_request_id: request.client_request_id,
+            reason,
+        };
+        debug!("rejected join request {:?}: {:?}", request, response);
+        HostToClientCommands::JoinGameRejected(response)
+    }
+
+    /// Creates new participants and adds them to the party of this connection.
+    /// A party is created if the connection does not have one yet.
+    ///
+    /// Players that would exceed the `max_participants` of the session take over
+    /// the participants of bots instead.
+    ///
+    /// Returns `None` if there are no free participant or party ids, or not enough bots, left.
+    /// Nothing is changed in the session then.
+    fn join_new_participants(
+        &mut self,
+        session: &mut GameSession<StepT>,
+        request: &JoinGameRequest,
+        metadata: Vec<ParticipantMetadata>,
+    ) -> Result<Option<Vec<Rc<RefCell<Participant>>>>, HostLogicError> {
+        let local_indices: Vec<_> = request
+            .player_requests
+            .players
+            .iter()
+            .map(|p| p.local_index)
+            .collect();
+
+        let replaced_bot_count = (session.participants.len() + local_indices.len())
+            .saturating_sub(session.participant_limits.max_participants)
+            .min(local_indices.len());
+        let (new_local_indices, bot_local_indices) =
+            local_indices.split_at(local_indices.len() - replaced_bot_count);
+
+        if session.participant_ids.len() < new_local_indices.len()
+            || session.bot_count() < bot_local_indices.len()
+            || (self.party_id.is_none() && session.party_ids.is_empty())
+        {
+            return Ok(None);
+        }
+
+        let party_id = match self.party_id {
+            Some(party_id) => party_id,
+            None => {
+                let party_id = session.create_party()?.id;
+                self.party_id = Some(party_id);
+                party_id
+            }
+        };
+
+        let mut participants = session
+            .create_participants(new_local_indices)
+            .ok_or(HostLogicError::NoFreeParticipantIds)?;
+        for participant in &participants {
+            session.combinator.create_buffer(participant.borrow().id);
+        }
+
+        let replaced_bots = session
+            .replace_bots(bot_local_indices)
+            .ok_or(HostLogicError::SessionFull)?;
+        participants.extend(replaced_bots);
+
+        for (participant, metadata) in participants.iter().zip(metadata) {
+            participant.borrow_mut().metadata = metadata;
+        }
+
+        for participant in &participants {
+            let participant_id = participant.borrow().id;
+            session
+                .parties
+                .get_mut(&party_id)
+                .ok_or(HostLogicError::UnknownParty(party_id))?
+                .participant_ids
+                .push(participant_id);
+        }
+
+        Ok(Some(participants))
+    }
+
+    /// Answers the ping with a pong right away, unless the host sends to the connection on its
+    /// own schedule, in which case the pong is included in the next [`Connection::send`].
+    pub(crate) fn on_ping(
+        &mut self,
+        now: Millis,
+        lower_millis: u16,
+    ) -> Vec<HostToClientCommands<Step<StepT>>> {
+        if self.is_sent_to_on_schedule {
+            self.pending_ping = Some((now, lower_millis));
+            return vec![];
+        }
+        vec![HostToClientCommands::Pong(PongInfo { lower_millis })]
+    }
+
+    /// Pauses or resumes the session on request from the client. Only connections with
+    /// joined participants can pause, and only if the host allows clients to pause.
+    pub(crate) fn on_session_pause(
+        &mut self,
+        session: &mut GameSession<StepT>,
+        clients_can_pause: bool,
+        request: &SessionPauseRequest,
+        now: Millis,
+    ) -> Result<HostToClientCommands<Step<StepT>>, HostLogicError> {
+        debug!("on_session_pause {:?}", request);
+
+        if !clients_can_pause || self.participant_lookup.is_empty() {
+            return Err(HostLogicError::ClientPauseNotAllowed);
+        }
+
+        if request.paused {
+            session.pause();
+        } else {
+            session.resume(now);
+        }
+
+        Ok(HostToClientCommands::GameStep(
+            self.game_step_response(session, now)?,
+        ))
+    }
+
+    pub(crate) fn on_leave(
+        &mut self,
+        session: &mut GameSession<StepT>,
+        request: &LeaveGameRequest,
+    ) -> Result<HostToClientCommands<Step<StepT>>, HostLogicError> {
+        debug!("on_leave {:?}", request);
+
+        let mut leaving_participant_ids = Vec::with_capacity(request.local_indices.len());
+        for local_index in &request.local_indices {
+            let participant_id = self
+                .participant_lookup
+                .values()
+                .find(|participant| participant.borrow().client_local_index == *local_index)
+                .map(|participant| participant.borrow().id)
+                .ok_or(HostLogicError::UnknownLocalIndex(*local_index))?;
+            if !leaving_participant_ids.contains(&participant_id) {
+                leaving_participant_ids.push(participant_id);
+            }
+        }
+
+        let mut left_participants = Vec::with_capacity(leaving_participant_ids.len());
+        for participant_id in leaving_participant_ids {
+            self.participant_lookup.remove(&participant_id);
+            let participant = session.remove_participant(participant_id)?;
+            left_participants.push(JoinGameParticipant {
+                local_index: participant.borrow().client_local_index,
+                participant_id,
+            });
+        }
+
+        let leave_accepted = LeaveGameAccepted {
+            client_request_id: request.client_request_id,
+            participants: JoinGameParticipants(left_participants),
+        };
+
+        Ok(HostToClientCommands::LeaveGame(leave_accepted))
+    }
+
+    /// Starts sending the game state to the client. The game state is shared with other
+    /// connections through the download cache of the session, and the authoritative steps
+    /// from the tick of the game state are kept for the client. Download requests over the
+    /// `max_download_requests_per_second` of the `rate_limits` are ignored.
     pub(crate) fn on_download(
         &mut self,
-        tick_id_to_be_produced: TickId,
+        session: &mut GameSession<StepT>,
         now: Millis,
+        rate_limits: &RateLimits,
+        blob_stream_settings: &BlobStreamOutSettings,
         request: &DownloadGameStateRequest,
-        state_provider: &impl GameStateProvider,
+        state_provider: &impl SessionGameStateProvider,
     ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
-        const FIXED_CHUNK_SIZE: u16 = 1024;
-        const RESEND_DURATION: Duration = Duration::from_millis(32 * 3);
-
         debug!("client requested download {:?}", request);
-        let (state_tick_id, state_vec) = state_provider.state(tick_id_to_be_produced);
+        if !self.rate_limiter.allow_download_request(now, rate_limits) {
+            return Ok(vec![]);
+        }
+        let tick_id_to_be_produced = session.combinator.tick_id_to_produce();
+        let oldest_tick_id = session.combinator.oldest_authoritative_tick_id();
+        let (state_tick_id, state) =
+            session
+                .download_cache
+                .get_or_insert_with(now, oldest_tick_id, || {
+                    state_provider.session_state(self.session_id, tick_id_to_be_produced)
+                });
+
+        // Keep the authoritative steps from the tick of the game state, until the client
+        // acknowledges them, so it can catch up without a gap
+        self.waiting_for_tick_id = Some(state_tick_id);
 
         let is_new_request = self
             .blob_stream_for_client_request
-            .map_or(true, |x| x == request.request_id);
+            .is_none_or(|x| x == request.request_id);
         if is_new_request {
             self.last_transfer_id += 1;
             let transfer_id = TransferId(self.last_transfer_id);
-            self.out_blob_stream = Some(OutLogicFront::new(
+            self.out_blob_stream = Some(OutLogicFront::from_shared(
                 transfer_id,
-                FIXED_CHUNK_SIZE,
-                RESEND_DURATION,
-                state_vec.as_slice(),
+                blob_stream_settings,
+                state,
             )?);
+            self.events
+                .push(ConnectionEvent::DownloadStarted(state_tick_id));
         }
 
         let response = DownloadGameStateResponse {
@@ -219,13 +607,121 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
         Ok(commands)
     }
 
-    pub(crate) fn on_steps(
+    /// Measures the incoming step buffers for the participants of this connection.
+    ///
+    /// Returns the number of buffered steps and how many ticks ahead of the tick to produce
+    /// the buffer reaches. The participant that is furthest behind decides both values,
+    /// since that is the one that risks getting forced steps.
+    fn buffer_diagnostics(&self, combinator: &HostCombinator<StepT>) -> (u8, i8) {
+        let tick_id_to_produce = combinator.tick_id_to_produce();
+
+        self.participant_lookup
+            .keys()
+            .filter_map(|participant_id| combinator.get(*participant_id))
+            .map(|buffer| {
+                let count = u8::try_from(buffer.len()).unwrap_or(u8::MAX);
+                let delta = (buffer.expected_write_tick_id() - tick_id_to_produce)
+                    .clamp(i64::from(i8::MIN), i64::from(i8::MAX))
+                    as i8;
+                (count, delta)
+            })
+            .min_by_key(|(_, delta)| *delta)
+            .unwrap_or((0, 0))
+    }
+
+    fn step_octet_count(step_map: &StepMap<Step<StepT>>) -> Result<usize, HostLogicError> {
+        let mut out_stream = OutOctetStream::new();
+        for step in step_map.values() {
+            step.serialize(&mut out_stream)?;
+        }
+        Ok(out_stream.octets_ref().len())
+    }
+
+    /// Collects the authoritative steps that the remote is waiting for.
+    ///
+    /// A new range is started whenever the participants change, so each participant has a
+    /// step for every tick in a range. The ranges are capped by [`AUTHORITATIVE_STEPS_OCTET_BUDGET`],
+    /// but at least one step is included.
+    fn authoritative_ranges_to_send(
         &self,
-        combinator: &mut HostCombinator<StepT>,
+        authoritative_steps: &Queue<StepMap<Step<StepT>>>,
+    ) -> Result<Vec<CombinedSteps<Step<StepT>>>, HostLogicError> {
+        const RANGE_OCTET_COUNT: usize = 2;
+        const PARTICIPANT_IN_RANGE_OCTET_COUNT: usize = 3;
+
+        let Some(front_tick_id) = authoritative_steps.front_tick_id() else {
+            return Ok(vec![]);
+        };
+        let start_tick_id = self
+            .waiting_for_tick_id
+            .map_or(front_tick_id, |waiting_for| waiting_for.max(front_tick_id));
+
+        let mut ranges: Vec<CombinedSteps<Step<StepT>>> = vec![];
+        let mut range_participant_ids = vec![];
+        let mut octet_count = 0;
+
+        for item_info in authoritative_steps
+            .iter()
+            .skip_while(|item_info| item_info.tick_id < start_tick_id)
+        {
+            let mut participant_ids: Vec<ParticipantId> = item_info.item.keys().copied().collect();
+            participant_ids.sort();
+
+            let needs_new_range = ranges.last().is_none_or(|range| {
+                range_participant_ids != participant_ids || range.steps.len() >= u8::MAX as usize
+            });
+
+            let mut needed_octet_count = Self::step_octet_count(&item_info.item)?;
+            if needs_new_range {
+                needed_octet_count +=
+                    RANGE_OCTET_COUNT + participant_ids.len() * PARTICIPANT_IN_RANGE_OCTET_COUNT;
+            }
+
+            let is_first_step = ranges.is_empty();
+            if !is_first_step
+                && (octet_count + needed_octet_count > AUTHORITATIVE_STEPS_OCTET_BUDGET
+                    || (needs_new_range && ranges.len() >= u8::MAX as usize))
+            {
+                break;
+            }
+            octet_count += needed_octet_count;
+
+            if needs_new_range {
+                ranges.push(CombinedSteps {
+                    tick_id: item_info.tick_id,
+                    steps: vec![],
+                });
+                range_participant_ids = participant_ids;
+            }
+
+            ranges
+                .last_mut()
+                .expect("a range was pushed above")
+                .steps
+                .push(item_info.item.clone());
+        }
+
+        Ok(ranges)
+    }
+
+    /// Adds the predicted steps to the buffers of the participants, after they have been
+    /// checked by the `validator`. A rejected step discards the rest of the steps for the
+    /// participant in the request, since the buffer must be contiguous.
+    ///
+    /// Steps beyond the `max_predicted_steps_ahead` of the `rate_limits` are discarded.
+    pub(crate) fn on_steps(
+        &mut self,
+        session: &mut GameSession<StepT>,
+        validator: &mut dyn StepValidator<StepT>,
+        violation_settings: &StepViolationSettings,
+        rate_limits: &RateLimits,
         request: &StepsRequest<StepT>,
+        now: Millis,
     ) -> Result<HostToClientCommands<Step<StepT>>, HostLogicError> {
         trace!("on incoming predicted steps {}", request);
 
+        self.waiting_for_tick_id = Some(request.ack.waiting_for_tick_id);
+
         /*
                                let mut tick = add_steps_request.combined_predicted_steps.tick_id;
                        for combined_step in &add_steps_request.combined_predicted_steps.steps {
@@ -241,52 +737,159 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + std::fmt::Display> Co
                        }
         */
 
+        let mut rejected_participant_ids = HashSet::new();
+        let too_far_ahead_tick_id =
+            session.combinator.tick_id_to_produce() + rate_limits.max_predicted_steps_ahead;
         let mut current_tick = request.combined_predicted_steps.tick_id;
         for combined_predicted_step in &request.combined_predicted_steps.steps {
-            for participant_id in combined_predicted_step.keys() {
-                // TODO:
-                if self.participant_lookup.contains_key(participant_id) {
-                    let part = combined_predicted_step.get(participant_id).unwrap();
-
-                    let buffer = combinator
-                        .get_mut(*participant_id)
-                        .expect("since the participant lookup worked, there should be a buffer");
-                    if buffer.expected_write_tick_id() != current_tick {
-                        continue;
-                    }
-                    buffer.push(current_tick, part.clone())?;
-                } else {
+            if current_tick >= too_far_ahead_tick_id {
+                self.rate_limiter
+                    .violations
+                    .push(RateLimitViolation::PredictedStepsAhead(current_tick));
+                break;
+            }
+            for (participant_id, step) in combined_predicted_step {
+                if rejected_participant_ids.contains(participant_id) {
+                    continue;
+                }
+                let Some(participant) = self.participant_lookup.get(participant_id) else {
                     return Err(HostLogicError::UnknownPartyMember(*participant_id));
+                };
+                let is_dropped = violation_settings.action == StepViolationAction::Drop
+                    && participant.borrow().step_violations >= violation_settings.max_violations;
+                if is_dropped {
+                    continue;
+                }
+
+                // Participants with forced steps have no buffer
+                let Some(buffer) = session.combinator.get_mut(*participant_id) else {
+                    continue;
+                };
+                if buffer.expected_write_tick_id() != current_tick {
+                    continue;
+                }
+                match validator.validate(*participant_id, current_tick, step) {
+                    StepValidation::Accept => buffer.push(current_tick, step.clone())?,
+                    StepValidation::Sanitize(sanitized_step) => {
+                        buffer.push(current_tick, sanitized_step)?;
+                    }
+                    StepValidation::Reject => {
+                        rejected_participant_ids.insert(*participant_id);
+                        self.on_step_violation(session, *participant_id, violation_settings)?;
+                    }
                 }
             }
             current_tick += 1;
         }
 
-        let authoritative_steps = combinator.authoritative_steps();
+        let game_step_response = self.game_step_response(session, now)?;
+
+        trace!("sending auth steps: {}", game_step_response);
+        Ok(HostToClientCommands::GameStep(game_step_response))
+    }
+
+    /// Counts a rejected step for the participant, and takes the action of the
+    /// `violation_settings` when the participant reaches the `max_violations`.
+    fn on_step_violation(
+        &mut self,
+        session: &mut GameSession<StepT>,
+        participant_id: ParticipantId,
+        violation_settings: &StepViolationSettings,
+    ) -> Result<(), HostLogicError> {
+        let step_violations = {
+            let mut participant = self
+                .participant_lookup
+                .get(&participant_id)
+                .ok_or(HostLogicError::UnknownPartyMember(participant_id))?
+                .borrow_mut();
+            participant.step_violations += 1;
+            participant.step_violations
+        };
+        debug!("rejected step from {participant_id}, violation count: {step_violations}");
+
+        if step_violations != violation_settings.max_violations {
+            return Ok(());
+        }
+
+        match violation_settings.action {
+            StepViolationAction::Drop => {}
+            StepViolationAction::Force => session.combinator.force_steps(participant_id),
+            StepViolationAction::Kick => {
+                self.participant_lookup.remove(&participant_id);
+                session.remove_participant(participant_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn game_step_response(
+        &self,
+        session: &GameSession<StepT>,
+        now: Millis,
+    ) -> Result<GameStepResponse<Step<StepT>>, HostLogicError> {
+        let combinator = &session.combinator;
+        let (connection_buffer_count, delta_buffer) = self.buffer_diagnostics(combinator);
 
         let combined_steps_vec =
-            authoritative_steps
-                .front_tick_id()
-                .map_or(Vec::new(), |found_first_tick_id| {
-                    let combined_steps = CombinedSteps::<Step<StepT>> {
-                        tick_id: found_first_tick_id,
-                        steps: authoritative_steps.to_vec(),
-                    };
-                    vec![combined_steps]
-                });
+            self.authoritative_ranges_to_send(combinator.authoritative_steps())?;
 
-        let game_step_response = GameStepResponse {
+        Ok(GameStepResponse {
             response_header: GameStepResponseHeader {
-                connection_buffer_count: 0,
-                delta_buffer: 0,
+                connection_buffer_count,
+                delta_buffer,
                 next_expected_tick_id: combinator.tick_id_to_produce(),
+                session_paused_at: combinator.paused_at(),
+                session_resume_in_millis: session.resume_in_millis(now),
             },
             authoritative_steps: AuthoritativeStepRanges {
                 ranges: combined_steps_vec,
             },
-        };
+        })
+    }
 
-        trace!("sending auth steps: {}", game_step_response);
-        Ok(HostToClientCommands::GameStep(game_step_response))
+    /// Creates the commands that the host sends on its own schedule, without a request from the client.
+    ///
+    /// Includes a pong for the latest ping, the authoritative steps that the remote has not
+    /// acknowledged yet and the blob stream chunks that should be sent or resent.
+    ///
+    /// The time the ping was held is added to the lower millis of the pong, so the latency
+    /// that the client measures does not include the wait for the schedule.
+    pub(crate) fn send(
+        &mut self,
+        now: Millis,
+        session: &GameSession<StepT>,
+    ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
+        self.is_sent_to_on_schedule = true;
+
+        let mut commands = vec![];
+
+        if let Some((received_at, lower_millis)) = self.pending_ping.take() {
+            let held_millis = now
+                .checked_duration_since_ms(received_at)
+                .map_or(0, |held| held.as_millis() as u16);
+            commands.push(HostToClientCommands::Pong(PongInfo {
+                lower_millis: lower_millis.wrapping_add(held_millis),
+            }));
+        }
+
+        if let Some(blob_stream) = self.out_blob_stream.as_mut() {
+            if !blob_stream.is_received_by_remote() {
+                commands.extend(
+                    blob_stream
+                        .send(now)?
+                        .into_iter()
+                        .map(HostToClientCommands::BlobStreamChannel),
+                );
+            }
+        }
+
+        let game_step_response = self.game_step_response(session, now)?;
+        if !game_step_response.authoritative_steps.ranges.is_empty() {
+            trace!("pushing auth steps: {}", game_step_response);
+            commands.push(HostToClientCommands::GameStep(game_step_response));
+        }
+
+        Ok(commands)
     }
 }
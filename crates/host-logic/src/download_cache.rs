@@ -0,0 +1,132 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use log::trace;
+use monotonic_time_rs::{Millis, MillisDuration};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use tick_id::TickId;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DownloadCacheSettings {
+    /// How long a game state is served to new downloads after it was created.
+    pub expiry: MillisDuration,
+    /// The maximum number of octets of game state in the cache. The oldest game states are
+    /// removed first, and a game state larger than this is never cached.
+    pub max_octet_size: usize,
+}
+
+impl Default for DownloadCacheSettings {
+    fn default() -> Self {
+        Self {
+            expiry: MillisDuration::from_millis(1000),
+            max_octet_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedState {
+    created_at: Millis,
+    blob: Rc<[u8]>,
+}
+
+/// The game states of a session, keyed by their tick, that are shared by every connection
+/// that downloads within the expiry. Saves serializing the same game state for each
+/// connection when many players join at once.
+#[derive(Debug, Default)]
+pub struct DownloadCache {
+    settings: DownloadCacheSettings,
+    states: BTreeMap<TickId, CachedState>,
+    octet_size: usize,
+}
+
+impl DownloadCache {
+    #[must_use]
+    pub fn new(settings: DownloadCacheSettings) -> Self {
+        Self {
+            settings,
+            states: BTreeMap::new(),
+            octet_size: 0,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: DownloadCacheSettings) {
+        self.settings = settings;
+        self.evict_to_max_octet_size();
+    }
+
+    #[must_use]
+    pub const fn settings(&self) -> &DownloadCacheSettings {
+        &self.settings
+    }
+
+    /// The number of octets of game state in the cache.
+    #[must_use]
+    pub const fn octet_size(&self) -> usize {
+        self.octet_size
+    }
+
+    /// Returns the newest game state that has not expired, or creates and caches a new one
+    /// with `create_state`.
+    ///
+    /// Game states before `oldest_tick_id` are never served, since the authoritative steps
+    /// that the client needs to catch up from them are no longer kept.
+    pub fn get_or_insert_with(
+        &mut self,
+        now: Millis,
+        oldest_tick_id: TickId,
+        create_state: impl FnOnce() -> (TickId, Vec<u8>),
+    ) -> (TickId, Rc<[u8]>) {
+        self.remove_unusable(now, oldest_tick_id);
+
+        if let Some((tick_id, cached_state)) = self.states.last_key_value() {
+            trace!("serving cached game state for {tick_id}");
+            return (*tick_id, cached_state.blob.clone());
+        }
+
+        let (tick_id, state) = create_state();
+        let blob: Rc<[u8]> = Rc::from(state);
+        if blob.len() <= self.settings.max_octet_size {
+            self.octet_size += blob.len();
+            if let Some(replaced_state) = self.states.insert(
+                tick_id,
+                CachedState {
+                    created_at: now,
+                    blob: blob.clone(),
+                },
+            ) {
+                self.octet_size -= replaced_state.blob.len();
+            }
+            self.evict_to_max_octet_size();
+        }
+
+        (tick_id, blob)
+    }
+
+    fn remove_unusable(&mut self, now: Millis, oldest_tick_id: TickId) {
+        let expiry = self.settings.expiry;
+        let mut removed_octet_size = 0;
+        self.states.retain(|tick_id, cached_state| {
+            let is_unusable = *tick_id < oldest_tick_id
+                || now
+                    .checked_duration_since_ms(cached_state.created_at)
+                    .is_some_and(|age| age >= expiry);
+            if is_unusable {
+                removed_octet_size += cached_state.blob.len();
+            }
+            !is_unusable
+        });
+        self.octet_size -= removed_octet_size;
+    }
+
+    fn evict_to_max_octet_size(&mut self) {
+        while self.octet_size > self.settings.max_octet_size {
+            let Some((_, oldest_state)) = self.states.pop_first() else {
+                break;
+            };
+            self.octet_size -= oldest_state.blob.len();
+        }
+    }
+}
@@ -10,44 +10,82 @@ use err_rs::{ErrorLevel, ErrorLevelProvider};
 use freelist_rs::FreeListError;
 use nimble_blob_stream::out_stream::OutStreamError;
 use nimble_participant::ParticipantId;
+use nimble_protocol::SessionId;
+use std::io;
 use tick_queue::QueueError;
 
 #[derive(Debug)]
 pub enum HostLogicError {
     UnknownConnectionId(HostConnectionId),
+    UnknownSession(SessionId),
+    SessionAlreadyExists(SessionId),
+    DefaultSessionCanNotBeRemoved,
     FreeListError {
         connection_id: HostConnectionId,
         message: FreeListError,
     },
+    ParticipantFreeListError {
+        participant_id: ParticipantId,
+        message: FreeListError,
+    },
+    PartyFreeListError {
+        party_id: u8,
+        message: FreeListError,
+    },
     UnknownPartyMember(ParticipantId),
+    UnknownParty(u8),
+    SessionFull,
+    UnknownBot(ParticipantId),
+    NoFreePartyIds,
+    UnknownLocalIndex(u8),
     NoFreeParticipantIds,
     BlobStreamErr(OutStreamError),
     NoDownloadNow,
     CombinatorError(CombinatorError),
     HostCombinatorError(HostCombinatorError),
     NeedConnectRequestFirst,
+    ClientPauseNotAllowed,
     WrongApplicationVersion,
     QueueError(QueueError),
+    IoError(io::Error),
 }
 
 impl ErrorLevelProvider for HostLogicError {
     fn error_level(&self) -> ErrorLevel {
         match self {
             Self::UnknownConnectionId(_) => ErrorLevel::Warning,
+            Self::UnknownSession(_) => ErrorLevel::Warning,
+            Self::SessionAlreadyExists(_) => ErrorLevel::Warning,
+            Self::DefaultSessionCanNotBeRemoved => ErrorLevel::Warning,
             Self::FreeListError { .. } => ErrorLevel::Critical,
+            Self::ParticipantFreeListError { .. } => ErrorLevel::Critical,
+            Self::PartyFreeListError { .. } => ErrorLevel::Critical,
             Self::UnknownPartyMember(_) => ErrorLevel::Warning,
+            Self::UnknownParty(_) => ErrorLevel::Critical,
+            Self::SessionFull => ErrorLevel::Warning,
+            Self::UnknownBot(_) => ErrorLevel::Warning,
+            Self::NoFreePartyIds => ErrorLevel::Warning,
+            Self::UnknownLocalIndex(_) => ErrorLevel::Warning,
             Self::NoFreeParticipantIds => ErrorLevel::Warning,
             Self::BlobStreamErr(_) => ErrorLevel::Info,
             Self::NoDownloadNow => ErrorLevel::Info,
             Self::CombinatorError(err) => err.error_level(),
             Self::HostCombinatorError(err) => err.error_level(),
             Self::NeedConnectRequestFirst => ErrorLevel::Info,
+            Self::ClientPauseNotAllowed => ErrorLevel::Warning,
             Self::WrongApplicationVersion => ErrorLevel::Critical,
             Self::QueueError(_) => ErrorLevel::Critical,
+            Self::IoError(_) => ErrorLevel::Warning,
         }
     }
 }
 
+impl From<io::Error> for HostLogicError {
+    fn from(err: io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
 impl From<CombinatorError> for HostLogicError {
     fn from(err: CombinatorError) -> Self {
         Self::CombinatorError(err)
@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use crate::rate_limit::RateLimitViolation;
+use crate::HostConnectionId;
+use err_rs::ErrorLevel;
+use nimble_participant::ParticipantId;
+use nimble_protocol::SessionId;
+use tick_id::TickId;
+
+/// Something that happened inside the `HostLogic`, for the game code and telemetry to react to.
+///
+/// Events are queued until they are drained with `HostLogic::drain_events`.
+#[derive(Debug, Clone)]
+pub enum HostEvent {
+    ConnectionCreated {
+        connection_id: HostConnectionId,
+    },
+    /// The connect request of the connection was accepted.
+    Connected {
+        connection_id: HostConnectionId,
+        session_id: SessionId,
+    },
+    ConnectionDestroyed {
+        connection_id: HostConnectionId,
+    },
+    /// A participant was added to the session, by a join game request or as a bot.
+    ParticipantJoined {
+        session_id: SessionId,
+        participant_id: ParticipantId,
+    },
+    /// A participant was removed from the session, e.g. it left, timed out or was kicked.
+    ParticipantLeft {
+        session_id: SessionId,
+        participant_id: ParticipantId,
+    },
+    /// The connection started to download the game state from `tick_id`.
+    DownloadStarted {
+        connection_id: HostConnectionId,
+        tick_id: TickId,
+    },
+    /// The client has received the whole game state.
+    DownloadCompleted {
+        connection_id: HostConnectionId,
+    },
+    /// `Step::Forced` was produced for the participant, since it had no step for the tick.
+    ForcedStep {
+        session_id: SessionId,
+        participant_id: ParticipantId,
+        tick_id: TickId,
+    },
+    /// A command from the connection could not be handled.
+    ProtocolError {
+        connection_id: HostConnectionId,
+        error_level: ErrorLevel,
+        message: String,
+    },
+    RateLimitExceeded {
+        connection_id: HostConnectionId,
+        violation: RateLimitViolation,
+    },
+}
+
+/// Events that a [`crate::connection::Connection`] queues up until the `HostLogic` reports
+/// them with the connection identifier.
+#[derive(Debug, Clone)]
+pub(crate) enum ConnectionEvent {
+    DownloadStarted(TickId),
+    DownloadCompleted,
+}
+
+/// Events that a [`crate::session::GameSession`] queues up until the `HostLogic` reports
+/// them with the session identifier.
+#[derive(Debug, Clone)]
+pub(crate) enum SessionEvent {
+    ParticipantJoined(ParticipantId),
+    ParticipantLeft(ParticipantId),
+    ForcedStep(TickId, ParticipantId),
+}
@@ -2,25 +2,45 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
+pub mod authenticator;
+pub mod bot;
 pub mod combinator;
 mod combine;
 pub mod connection;
+pub mod download_cache;
 pub mod err;
+pub mod event;
+pub mod missing_step;
+pub mod rate_limit;
 pub mod session;
+pub mod tick_clock;
+pub mod validator;
 
+use crate::authenticator::{AcceptAllAuthenticator, Authenticator};
+use crate::bot::Bot;
 use crate::connection::Connection;
+use crate::download_cache::{DownloadCache, DownloadCacheSettings};
 use crate::err::HostLogicError;
-use crate::session::GameSession;
+use crate::event::{ConnectionEvent, HostEvent, SessionEvent};
+use crate::missing_step::MissingStepPolicy;
+use crate::rate_limit::RateLimits;
+use crate::session::{GameSession, ParticipantLimits, DEFAULT_RESUME_DELAY};
+use crate::tick_clock::TickSettings;
+use crate::validator::{AcceptAllStepValidator, StepValidator, StepViolationSettings};
 use app_version::Version;
+use err_rs::ErrorLevelProvider;
 use flood_rs::{Deserialize, Serialize};
 use freelist_rs::FreeList;
-use log::trace;
-use monotonic_time_rs::Millis;
-use nimble_protocol::host_to_client::PongInfo;
+use log::{debug, trace};
+use monotonic_time_rs::{Millis, MillisDuration};
+use nimble_blob_stream::prelude::BlobStreamOutSettings;
+use nimble_participant::ParticipantId;
+use nimble_protocol::client_to_host::{JoinGameRequest, JoinGameType};
+use nimble_protocol::host_to_client::ConnectionRejectedReason;
 use nimble_protocol::prelude::{ClientToHostCommands, HostToClientCommands};
-use nimble_protocol::NIMBLE_PROTOCOL_VERSION;
+use nimble_protocol::{SessionConnectionSecret, SessionId, NIMBLE_PROTOCOL_VERSION};
 use nimble_step::Step;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Display};
 use tick_id::TickId;
 
@@ -28,6 +48,23 @@ pub trait GameStateProvider {
     fn state(&self, tick_id: TickId) -> (TickId, Vec<u8>);
 }
 
+/// Provides the game state for each of the game sessions on the host.
+///
+/// Every [`GameStateProvider`] is also a `SessionGameStateProvider` that provides
+/// the same state for all sessions, which is enough for a host with a single session.
+pub trait SessionGameStateProvider {
+    fn session_state(&self, session_id: SessionId, tick_id: TickId) -> (TickId, Vec<u8>);
+}
+
+impl<T: GameStateProvider> SessionGameStateProvider for T {
+    fn session_state(&self, _: SessionId, tick_id: TickId) -> (TickId, Vec<u8>) {
+        self.state(tick_id)
+    }
+}
+
+/// The session that is created together with the [`HostLogic`].
+pub const DEFAULT_SESSION_ID: SessionId = SessionId(0);
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Phase {
     WaitingForValidConnectRequest,
@@ -52,17 +89,30 @@ pub struct HostConnectionId(pub u8);
 pub struct HostLogic<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> {
     #[allow(unused)]
     connections: HashMap<u8, Connection<StepT>>,
-    session: GameSession<StepT>,
+    sessions: HashMap<SessionId, GameSession<StepT>>,
     free_list: FreeList<u8>,
     deterministic_simulation_version: Version,
+    tick_settings: TickSettings,
+    participant_limits: ParticipantLimits,
+    reconnect_timeout: MillisDuration,
+    max_connection_count: usize,
+    authenticator: Box<dyn Authenticator>,
+    step_validator: Box<dyn StepValidator<StepT>>,
+    step_violation_settings: StepViolationSettings,
+    rate_limits: RateLimits,
+    download_cache_settings: DownloadCacheSettings,
+    blob_stream_settings: BlobStreamOutSettings,
+    events: VecDeque<HostEvent>,
+    clients_can_pause: bool,
+    resume_delay: MillisDuration,
 }
 
 impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<StepT> {
-    /// Creates a new instance of `HostLogic`.
+    /// Creates a new instance of `HostLogic` with a single game session, [`DEFAULT_SESSION_ID`].
     ///
     /// # Parameters
     ///
-    /// - `tick_id`: The initial tick identifier for the game session.
+    /// - `tick_id`: The initial tick identifier for the default game session.
     /// - `deterministic_simulation_version`: The version of the deterministic simulation.
     ///
     /// # Returns
@@ -72,12 +122,430 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
     pub fn new(tick_id: TickId, deterministic_simulation_version: Version) -> Self {
         Self {
             connections: HashMap::new(),
-            session: GameSession::new(tick_id),
+            sessions: [(DEFAULT_SESSION_ID, GameSession::new(tick_id))].into(),
             free_list: FreeList::<u8>::new(0xff),
             deterministic_simulation_version,
+            tick_settings: TickSettings::default(),
+            participant_limits: ParticipantLimits::default(),
+            reconnect_timeout: MillisDuration::from_millis(10_000),
+            clients_can_pause: false,
+            resume_delay: DEFAULT_RESUME_DELAY,
+            max_connection_count: 0xff,
+            authenticator: Box::new(AcceptAllAuthenticator),
+            step_validator: Box::new(AcceptAllStepValidator),
+            step_violation_settings: StepViolationSettings::default(),
+            rate_limits: RateLimits::default(),
+            download_cache_settings: DownloadCacheSettings::default(),
+            blob_stream_settings: BlobStreamOutSettings::default(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Sets the maximum number of connected connections. Connect requests beyond that are
+    /// rejected with `ConnectionRejectedReason::SessionFull`.
+    ///
+    /// # Parameters
+    ///
+    /// - `max_connection_count`: The maximum number of connected connections.
+    pub fn set_max_connection_count(&mut self, max_connection_count: usize) {
+        self.max_connection_count = max_connection_count;
+    }
+
+    /// Sets the authenticator that decides which connect and join game requests that are accepted.
+    /// Until it is set, every request is accepted.
+    ///
+    /// # Parameters
+    ///
+    /// - `authenticator`: The authenticator to consult for connect and join game requests.
+    pub fn set_authenticator(&mut self, authenticator: impl Authenticator + 'static) {
+        self.authenticator = Box::new(authenticator);
+    }
+
+    /// Sets the validator that accepts, sanitizes or rejects the predicted steps from clients.
+    /// Until it is set, every step is accepted.
+    ///
+    /// # Parameters
+    ///
+    /// - `step_validator`: The validator to consult for every incoming predicted step.
+    pub fn set_step_validator(&mut self, step_validator: impl StepValidator<StepT> + 'static) {
+        self.step_validator = Box::new(step_validator);
+    }
+
+    /// Sets how many rejected steps a participant can have, and what happens to it after that.
+    ///
+    /// # Parameters
+    ///
+    /// - `step_violation_settings`: The maximum number of violations and the action to take.
+    pub fn set_step_violation_settings(&mut self, step_violation_settings: StepViolationSettings) {
+        self.step_violation_settings = step_violation_settings;
+    }
+
+    /// Sets the budgets for what each connection can send to the host.
+    ///
+    /// # Parameters
+    ///
+    /// - `rate_limits`: The budgets for datagrams, commands, predicted steps and downloads.
+    pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+        self.rate_limits = rate_limits;
+    }
+
+    /// Retrieves the budgets for what each connection can send to the host.
+    #[must_use]
+    pub const fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
+    /// Sets how long game states are shared between downloading connections, and how much
+    /// memory they can use, for every session.
+    ///
+    /// # Parameters
+    ///
+    /// - `download_cache_settings`: The expiry and the maximum octet size of the cache.
+    pub fn set_download_cache_settings(&mut self, download_cache_settings: DownloadCacheSettings) {
+        self.download_cache_settings = download_cache_settings;
+        for session in self.sessions.values_mut() {
+            session.download_cache.set_settings(download_cache_settings);
+        }
+    }
+
+    /// Sets how the game state is sent to downloading connections. Downloads that have already
+    /// started keep their settings.
+    ///
+    /// # Parameters
+    ///
+    /// - `blob_stream_settings`: The chunk size, the resend timing and the number of chunks
+    ///   to send at a time.
+    pub fn set_blob_stream_settings(&mut self, blob_stream_settings: BlobStreamOutSettings) {
+        self.blob_stream_settings = blob_stream_settings;
+    }
+
+    /// Retrieves how the game state is sent to downloading connections.
+    #[must_use]
+    pub const fn blob_stream_settings(&self) -> &BlobStreamOutSettings {
+        &self.blob_stream_settings
+    }
+
+    /// Sets the limits for how many participants that can join each session. Join game requests
+    /// beyond them are answered with a `JoinGameRejected`.
+    ///
+    /// # Parameters
+    ///
+    /// - `participant_limits`: The participant limits for the sessions, connections and requests.
+    pub fn set_participant_limits(&mut self, participant_limits: ParticipantLimits) {
+        self.participant_limits = participant_limits;
+        for session in self.sessions.values_mut() {
+            session.participant_limits = participant_limits;
+        }
+    }
+
+    /// Retrieves the limits for how many participants that can join each session.
+    #[must_use]
+    pub const fn participant_limits(&self) -> &ParticipantLimits {
+        &self.participant_limits
+    }
+
+    /// Sets the policy that decides the steps for participants that have not provided a step
+    /// in time, e.g. repeating their last step or waiting for everyone in lockstep.
+    /// Until it is set, `Step::Forced` is produced for them.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to set the policy for.
+    /// - `policy`: The missing step policy for the session.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found.
+    pub fn set_missing_step_policy(
+        &mut self,
+        session_id: SessionId,
+        policy: impl MissingStepPolicy<StepT> + 'static,
+    ) -> Result<(), HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .set_missing_step_policy(policy);
+        Ok(())
+    }
+
+    /// Adds a custom step from the host application to the authoritative step of a tick,
+    /// under [`nimble_participant::HOST_PARTICIPANT_ID`]. Lets server side game logic drive
+    /// deterministic events, e.g. spawning, round timers and admin actions.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to add the step to.
+    /// - `tick_id`: The tick that the step should be produced for. Must not be produced yet.
+    /// - `step`: The custom step.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found, or
+    /// `HostLogicError::CombinatorError` if the tick has already been produced or already
+    /// has a host step.
+    pub fn push_host_step(
+        &mut self,
+        session_id: SessionId,
+        tick_id: TickId,
+        step: StepT,
+    ) -> Result<(), HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .combinator
+            .push_host_step(tick_id, step)?;
+        Ok(())
+    }
+
+    /// Adds a server side participant without a connection, that gets its steps from the bot.
+    /// The participant is announced with a `Step::Joined` like any other participant.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to add the bot to.
+    /// - `bot`: Provides the step of the participant for every produced tick.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found, or
+    /// `HostLogicError::SessionFull` if the session has reached its `max_participants`.
+    pub fn add_bot(
+        &mut self,
+        session_id: SessionId,
+        bot: impl Bot<StepT> + 'static,
+    ) -> Result<ParticipantId, HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .add_bot(bot)
+    }
+
+    /// Removes a bot participant from the session. A `Step::Left` is produced for it.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session.
+    /// - `participant_id`: The participant of the bot.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found, or
+    /// `HostLogicError::UnknownBot` if the participant is not a bot.
+    pub fn remove_bot(
+        &mut self,
+        session_id: SessionId,
+        participant_id: ParticipantId,
+    ) -> Result<(), HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .remove_bot(participant_id)
+    }
+
+    /// Stops producing authoritative steps for the session. Clients are told the tick
+    /// that the session is paused at, so they stop predicting further ahead.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to pause.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found.
+    pub fn pause_session(&mut self, session_id: SessionId) -> Result<(), HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .pause();
+        Ok(())
+    }
+
+    /// Resumes a paused session. The session continues from the tick it was paused at,
+    /// so every client restarts from the same tick. It resumes once the resume delay has
+    /// passed, see [`HostLogic::set_resume_delay`], and clients are told how long is left.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to resume.
+    /// - `now`: The current absolute time in milliseconds precision.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found.
+    pub fn resume_session(
+        &mut self,
+        session_id: SessionId,
+        now: Millis,
+    ) -> Result<(), HostLogicError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?
+            .resume(now);
+        Ok(())
+    }
+
+    /// Sets if clients with joined participants can pause and resume their session
+    /// with a session pause request. Clients can not pause by default.
+    ///
+    /// # Parameters
+    ///
+    /// - `clients_can_pause`: `true` if session pause requests should be accepted.
+    pub fn set_clients_can_pause(&mut self, clients_can_pause: bool) {
+        self.clients_can_pause = clients_can_pause;
+    }
+
+    /// Retrieves if clients can pause and resume their session.
+    #[must_use]
+    pub const fn clients_can_pause(&self) -> bool {
+        self.clients_can_pause
+    }
+
+    /// Sets how long a paused session waits after a resume request before it continues,
+    /// so every client knows in time when to start predicting. Applies to all sessions.
+    /// The default is 128 ms, zero resumes at once.
+    ///
+    /// # Parameters
+    ///
+    /// - `resume_delay`: The time to wait before resuming.
+    pub fn set_resume_delay(&mut self, resume_delay: MillisDuration) {
+        self.resume_delay = resume_delay;
+        for session in self.sessions.values_mut() {
+            session.resume_delay = resume_delay;
         }
     }
 
+    /// Retrieves how long a paused session waits before it resumes.
+    #[must_use]
+    pub const fn resume_delay(&self) -> MillisDuration {
+        self.resume_delay
+    }
+
+    /// Creates a new game session, with its own participants and authoritative timeline.
+    /// Clients choose the session in their connect request.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier that clients use to connect to the session.
+    /// - `tick_id`: The initial tick identifier for the game session.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::SessionAlreadyExists` if there is already a session with the identifier.
+    pub fn create_session(
+        &mut self,
+        session_id: SessionId,
+        tick_id: TickId,
+    ) -> Result<(), HostLogicError> {
+        if self.sessions.contains_key(&session_id) {
+            return Err(HostLogicError::SessionAlreadyExists(session_id));
+        }
+
+        let mut session = GameSession::new(tick_id);
+        session.participant_limits = self.participant_limits;
+        session.tick_clock.set_settings(self.tick_settings);
+        session.resume_delay = self.resume_delay;
+        session.download_cache = DownloadCache::new(self.download_cache_settings);
+        self.sessions.insert(session_id, session);
+
+        Ok(())
+    }
+
+    /// Removes a game session. The connections in the session must connect again.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to remove.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownSession` if the session is not found, or
+    /// `HostLogicError::DefaultSessionCanNotBeRemoved` for [`DEFAULT_SESSION_ID`].
+    pub fn remove_session(&mut self, session_id: SessionId) -> Result<(), HostLogicError> {
+        if session_id == DEFAULT_SESSION_ID {
+            return Err(HostLogicError::DefaultSessionCanNotBeRemoved);
+        }
+
+        self.sessions
+            .remove(&session_id)
+            .ok_or(HostLogicError::UnknownSession(session_id))?;
+
+        for connection in self.connections.values_mut() {
+            if connection.session_id == session_id {
+                connection.leave_session(None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bans the connection. Every connect request from it is rejected with
+    /// `ConnectionRejectedReason::Banned`, and a connected connection must connect again.
+    ///
+    /// # Parameters
+    ///
+    /// - `connection_id`: The `HostConnectionId` of the connection to ban.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownConnectionId` if the connection is not found.
+    pub fn ban_connection(
+        &mut self,
+        connection_id: HostConnectionId,
+    ) -> Result<(), HostLogicError> {
+        let connection = self
+            .connections
+            .get_mut(&connection_id.0)
+            .ok_or(HostLogicError::UnknownConnectionId(connection_id))?;
+        connection.banned = true;
+        if connection.phase == Phase::Connected {
+            connection.leave_session(self.sessions.get_mut(&connection.session_id))?;
+            self.report_session_events();
+        }
+        Ok(())
+    }
+
+    fn connected_count(&self) -> usize {
+        self.connections
+            .values()
+            .filter(|connection| connection.phase == Phase::Connected)
+            .count()
+    }
+
+    /// Sets for how long the participants of a destroyed connection are kept in the session,
+    /// waiting for the client to join again with the party secret.
+    ///
+    /// # Parameters
+    ///
+    /// - `reconnect_timeout`: The time to wait before the participants are removed.
+    pub fn set_reconnect_timeout(&mut self, reconnect_timeout: MillisDuration) {
+        self.reconnect_timeout = reconnect_timeout;
+    }
+
+    /// Retrieves for how long the participants of a destroyed connection are kept in the session.
+    #[must_use]
+    pub const fn reconnect_timeout(&self) -> MillisDuration {
+        self.reconnect_timeout
+    }
+
+    /// Sets the settings used by [`HostLogic::tick`] to produce authoritative steps on a clock,
+    /// for every session.
+    ///
+    /// # Parameters
+    ///
+    /// - `tick_settings`: The tick duration, catch-up limit and grace period to use.
+    pub fn set_tick_settings(&mut self, tick_settings: TickSettings) {
+        self.tick_settings = tick_settings;
+        for session in self.sessions.values_mut() {
+            session.tick_clock.set_settings(tick_settings);
+        }
+    }
+
+    /// Retrieves the settings used by [`HostLogic::tick`].
+    #[must_use]
+    pub const fn tick_settings(&self) -> &TickSettings {
+        &self.tick_settings
+    }
+
     /// Creates a new connection and returns its identifier.
     ///
     /// Allocates a unique `HostConnectionId` for a new client connection.
@@ -89,7 +557,10 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
         let new_connection_id = self.free_list.allocate();
         if let Some(id) = new_connection_id {
             self.connections.insert(id, Connection::new());
-            Some(HostConnectionId(id))
+            let connection_id = HostConnectionId(id);
+            self.events
+                .push_back(HostEvent::ConnectionCreated { connection_id });
+            Some(connection_id)
         } else {
             None
         }
@@ -111,6 +582,10 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
 
     /// Destroys a connection, freeing its identifier.
     ///
+    /// The participants that joined through the connection are kept in the session with
+    /// `Step::WaitingForReconnect` until the client joins again with the party secret, or until
+    /// the reconnect timeout has passed and they are removed with a `Step::Left`.
+    ///
     /// # Parameters
     ///
     /// - `connection_id`: The `HostConnectionId` of the connection to destroy.
@@ -126,6 +601,11 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
         &mut self,
         connection_id: HostConnectionId,
     ) -> Result<(), HostLogicError> {
+        let mut connection = self
+            .connections
+            .remove(&connection_id.0)
+            .ok_or(HostLogicError::UnknownConnectionId(connection_id))?;
+
         self.free_list
             .free(connection_id.0)
             .map_err(|err| HostLogicError::FreeListError {
@@ -133,28 +613,147 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
                 message: err,
             })?;
 
-        if self.connections.remove(&connection_id.0).is_some() {
-            Ok(())
-        } else {
-            Err(HostLogicError::UnknownConnectionId(connection_id))
-        }
+        connection.leave_session(self.sessions.get_mut(&connection.session_id))?;
+
+        self.events
+            .push_back(HostEvent::ConnectionDestroyed { connection_id });
+        self.report_session_events();
+
+        Ok(())
+    }
+
+    /// Retrieves a reference to the default game session.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the `GameSession` with [`DEFAULT_SESSION_ID`].
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn session(&self) -> &GameSession<StepT> {
+        self.sessions
+            .get(&DEFAULT_SESSION_ID)
+            .expect("the default session can not be removed")
     }
 
-    /// Retrieves a reference to the current game session.
+    /// Retrieves a reference to a game session by its identifier.
+    ///
+    /// # Parameters
+    ///
+    /// - `session_id`: The identifier of the session to retrieve.
     ///
     /// # Returns
     ///
-    /// A reference to the `GameSession`.
+    /// An `Option` containing a reference to the `GameSession` if found, or `None` otherwise.
     #[must_use]
-    pub const fn session(&self) -> &GameSession<StepT> {
-        &self.session
+    pub fn get_session(&self, session_id: SessionId) -> Option<&GameSession<StepT>> {
+        self.sessions.get(&session_id)
+    }
+
+    /// Retrieves the identifiers of all game sessions.
+    pub fn session_ids(&self) -> impl Iterator<Item = SessionId> + '_ {
+        self.sessions.keys().copied()
+    }
+
+    /// Produces authoritative steps on a fixed clock, for every session.
+    ///
+    /// Should be called regularly, even when no datagrams are received, so the authoritative
+    /// timelines keep advancing. Ticks that have passed their deadline, including the grace period,
+    /// are produced with the step from the missing step policy for every participant that has not
+    /// provided a step. At most `max_ticks_per_update` ticks are caught up each call.
+    /// The clock of a session starts at the first call after the session was created, and
+    /// restarts when the session is resumed. Paused sessions do not produce any steps, until
+    /// their scheduled resume is due.
+    ///
+    /// # Parameters
+    ///
+    /// - `now`: The current absolute time in milliseconds precision.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the authoritative steps could not be produced.
+    pub fn tick(&mut self, now: Millis) -> Result<(), HostLogicError> {
+        for session in self.sessions.values_mut() {
+            session.resume_if_due(now);
+            session.combinator.produce_authoritative_steps()?;
+
+            let deadline_tick_id = session.deadline_tick_id(now);
+            session
+                .combinator
+                .produce_authoritative_steps_until(deadline_tick_id)?;
+
+            session.remove_timed_out_parties(self.reconnect_timeout)?;
+        }
+
+        self.discard_acknowledged_authoritative_steps();
+        self.report_session_events();
+
+        Ok(())
     }
 
     /// Performs post-update operations after the main `update` cycle.
     ///
-    /// Specifically, it triggers the production of authoritative steps within the session's combinator.
-    pub fn post_update(&mut self) {
-        self.session.combinator.produce_authoritative_steps();
+    /// Specifically, it triggers the production of authoritative steps within the combinator of each session
+    /// and discards the authoritative steps that every connection has acknowledged.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the authoritative steps could not be produced.
+    pub fn post_update(&mut self) -> Result<(), HostLogicError> {
+        for session in self.sessions.values_mut() {
+            session.combinator.produce_authoritative_steps()?;
+        }
+        self.discard_acknowledged_authoritative_steps();
+        self.report_session_events();
+
+        Ok(())
+    }
+
+    /// Moves the party that owns the secret away from any other connection in the same session,
+    /// so a client can take over its participants from a connection that has not timed out yet.
+    /// Only done once the join with the secret has been accepted.
+    fn detach_party_from_other_connections(
+        &mut self,
+        connection_id: HostConnectionId,
+        session_id: SessionId,
+        secret: SessionConnectionSecret,
+    ) {
+        let Some(party_id) = self
+            .sessions
+            .get(&session_id)
+            .and_then(|session| session.find_party_by_secret(secret))
+            .map(|party| party.id)
+        else {
+            return;
+        };
+
+        for (other_connection_id, other_connection) in &mut self.connections {
+            if *other_connection_id != connection_id.0
+                && other_connection.session_id == session_id
+                && other_connection.party_id == Some(party_id)
+            {
+                other_connection.party_id = None;
+                other_connection.participant_lookup.clear();
+            }
+        }
+    }
+
+    /// Discards the authoritative steps that have been acknowledged by every connection in the session.
+    ///
+    /// Connections that have not acknowledged any authoritative steps yet are not considered.
+    fn discard_acknowledged_authoritative_steps(&mut self) {
+        for (session_id, session) in &mut self.sessions {
+            if let Some(lowest_waiting_for_tick_id) = self
+                .connections
+                .values()
+                .filter(|connection| connection.session_id == *session_id)
+                .filter_map(Connection::waiting_for_tick_id)
+                .min()
+            {
+                session
+                    .combinator
+                    .discard_authoritative_steps_up_to(lowest_waiting_for_tick_id);
+            }
+        }
     }
 
     /// Processes an update from a client connection.
@@ -166,7 +765,7 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
     /// - `connection_id`: The `HostConnectionId` of the client sending the commands.
     /// - `now`: The current absolute time in milliseconds precision.
     /// - `request`: The `ClientToHostCommands` sent by the client.
-    /// - `state_provider`: An implementation of `GameStateProvider` to supply game state data.
+    /// - `state_provider`: An implementation of `SessionGameStateProvider` to supply game state data.
     ///
     /// # Returns
     ///
@@ -181,26 +780,108 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
         connection_id: HostConnectionId,
         now: Millis,
         request: &ClientToHostCommands<StepT>,
-        state_provider: &impl GameStateProvider,
+        state_provider: &impl SessionGameStateProvider,
     ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
         trace!("host_logic: receive: \n{request}");
+        let was_connected = self
+            .connections
+            .get(&connection_id.0)
+            .is_some_and(|connection| connection.phase == Phase::Connected);
+
+        let result = self.handle_command(connection_id, now, request, state_provider);
+
+        if let Some(connection) = self.connections.get(&connection_id.0) {
+            if !was_connected && connection.phase == Phase::Connected {
+                self.events.push_back(HostEvent::Connected {
+                    connection_id,
+                    session_id: connection.session_id,
+                });
+            }
+        }
+        if let Err(err) = &result {
+            self.events.push_back(HostEvent::ProtocolError {
+                connection_id,
+                error_level: err.error_level(),
+                message: format!("{err:?}"),
+            });
+        }
+        self.report_connection_events(connection_id);
+        self.report_session_events();
+
+        result
+    }
+
+    fn handle_command(
+        &mut self,
+        connection_id: HostConnectionId,
+        now: Millis,
+        request: &ClientToHostCommands<StepT>,
+        state_provider: &impl SessionGameStateProvider,
+    ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
+        let result = self.handle_command_for_phase(connection_id, now, request, state_provider);
+
+        if let ClientToHostCommands::JoinGameType(JoinGameRequest {
+            join_game_type: JoinGameType::UseSessionSecret(secret),
+            ..
+        }) = request
+        {
+            let is_accepted = result.as_ref().is_ok_and(|commands| {
+                commands
+                    .iter()
+                    .any(|command| matches!(command, HostToClientCommands::JoinGame(_)))
+            });
+            let session_id = self
+                .connections
+                .get(&connection_id.0)
+                .map(|connection| connection.session_id);
+            if let (true, Some(session_id)) = (is_accepted, session_id) {
+                self.detach_party_from_other_connections(connection_id, session_id, *secret);
+            }
+        }
+
+        result
+    }
+
+    fn handle_command_for_phase(
+        &mut self,
+        connection_id: HostConnectionId,
+        now: Millis,
+        request: &ClientToHostCommands<StepT>,
+        state_provider: &impl SessionGameStateProvider,
+    ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
+        let is_session_full = matches!(request, ClientToHostCommands::ConnectType(_))
+            && self.connected_count() >= self.max_connection_count;
+
         if let Some(ref mut connection) = self.connections.get_mut(&connection_id.0) {
             match &connection.phase {
                 Phase::Connected => {
+                    let session = self
+                        .sessions
+                        .get_mut(&connection.session_id)
+                        .ok_or(HostLogicError::UnknownSession(connection.session_id))?;
                     match request {
-                        ClientToHostCommands::JoinGameType(join_game_request) => Ok(vec![
-                            connection.on_join(&mut self.session, join_game_request)?,
-                        ]),
-                        ClientToHostCommands::Steps(add_steps_request) => {
-                            Ok(vec![connection.on_steps(
-                                &mut self.session.combinator,
-                                add_steps_request,
+                        ClientToHostCommands::JoinGameType(join_game_request) => {
+                            Ok(vec![connection.on_join(
+                                session,
+                                self.authenticator.as_mut(),
+                                join_game_request,
                             )?])
                         }
+                        ClientToHostCommands::Steps(add_steps_request) => Ok(vec![connection
+                            .on_steps(
+                                session,
+                                self.step_validator.as_mut(),
+                                &self.step_violation_settings,
+                                &self.rate_limits,
+                                add_steps_request,
+                                now,
+                            )?]),
                         ClientToHostCommands::DownloadGameState(download_game_state_request) => {
                             Ok(connection.on_download(
-                                self.session.combinator.tick_id_to_produce(),
+                                session,
                                 now,
+                                &self.rate_limits,
+                                &self.blob_stream_settings,
                                 download_game_state_request,
                                 state_provider,
                             )?)
@@ -210,15 +891,48 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
                         }
                         ClientToHostCommands::ConnectType(connect_request) => {
                             trace!("notice: got connection request, even though we are connected, but will send response anyway");
-                            connection
-                                .on_connect(connect_request, &self.deterministic_simulation_version)
+                            // Changing session requires connecting again
+                            let session_rejected_reason = (connect_request.session_id
+                                != connection.session_id)
+                                .then_some(ConnectionRejectedReason::DifferentSession);
+                            let commands = connection.on_connect(
+                                connect_request,
+                                &self.deterministic_simulation_version,
+                                session_rejected_reason,
+                                false,
+                                self.authenticator.as_mut(),
+                            )?;
+                            if connection.phase != Phase::Connected {
+                                // A rejected connection takes no part in the session anymore
+                                connection.leave_session(Some(session))?;
+                            }
+                            Ok(commands)
+                        }
+                        ClientToHostCommands::Ping(ping_info) => {
+                            Ok(connection.on_ping(now, *ping_info))
+                        }
+                        ClientToHostCommands::LeaveGameType(leave_game_request) => {
+                            Ok(vec![connection.on_leave(session, leave_game_request)?])
+                        }
+                        ClientToHostCommands::SessionPause(session_pause_request) => {
+                            Ok(vec![connection.on_session_pause(
+                                session,
+                                self.clients_can_pause,
+                                session_pause_request,
+                                now,
+                            )?])
                         }
-                        ClientToHostCommands::Ping(ping_info) => Ok(Self::on_ping(*ping_info)),
                     }
                 }
                 Phase::WaitingForValidConnectRequest => match request {
-                    ClientToHostCommands::ConnectType(connect_request) => connection
-                        .on_connect(connect_request, &self.deterministic_simulation_version),
+                    ClientToHostCommands::ConnectType(connect_request) => connection.on_connect(
+                        connect_request,
+                        &self.deterministic_simulation_version,
+                        (!self.sessions.contains_key(&connect_request.session_id))
+                            .then_some(ConnectionRejectedReason::UnknownSession),
+                        is_session_full,
+                        self.authenticator.as_mut(),
+                    ),
                     _ => Err(HostLogicError::NeedConnectRequestFirst),
                 },
             }
@@ -227,7 +941,140 @@ impl<StepT: Clone + Eq + Debug + Deserialize + Serialize + Display> HostLogic<St
         }
     }
 
-    fn on_ping(lower_millis: u16) -> Vec<HostToClientCommands<Step<StepT>>> {
-        vec![HostToClientCommands::Pong(PongInfo { lower_millis })]
+    /// Counts an incoming datagram against the `RateLimits` of the connection, before its
+    /// commands are handled with [`HostLogic::update`].
+    ///
+    /// # Parameters
+    ///
+    /// - `connection_id`: The `HostConnectionId` of the client that sent the datagram.
+    /// - `now`: The current absolute time in milliseconds precision.
+    /// - `command_count`: The number of commands in the datagram.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the commands should be handled, or `false` if the datagram is over budget
+    /// and should be ignored. The violation is reported as a `HostEvent::RateLimitExceeded`.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::UnknownConnectionId` if the connection is not found.
+    pub fn allow_datagram(
+        &mut self,
+        connection_id: HostConnectionId,
+        now: Millis,
+        command_count: usize,
+    ) -> Result<bool, HostLogicError> {
+        let is_allowed = self
+            .connections
+            .get_mut(&connection_id.0)
+            .ok_or(HostLogicError::UnknownConnectionId(connection_id))?
+            .rate_limiter
+            .allow_datagram(now, command_count, &self.rate_limits);
+        self.report_connection_events(connection_id);
+        Ok(is_allowed)
+    }
+
+    fn report_connection_events(&mut self, connection_id: HostConnectionId) {
+        let Some(connection) = self.connections.get_mut(&connection_id.0) else {
+            return;
+        };
+        for event in connection.events.drain(..) {
+            self.events.push_back(match event {
+                ConnectionEvent::DownloadStarted(tick_id) => HostEvent::DownloadStarted {
+                    connection_id,
+                    tick_id,
+                },
+                ConnectionEvent::DownloadCompleted => {
+                    HostEvent::DownloadCompleted { connection_id }
+                }
+            });
+        }
+        for violation in connection.rate_limiter.violations.drain(..) {
+            debug!("connection {connection_id:?} exceeded rate limit {violation:?}");
+            self.events.push_back(HostEvent::RateLimitExceeded {
+                connection_id,
+                violation,
+            });
+        }
+    }
+
+    fn report_session_events(&mut self) {
+        for (session_id, session) in &mut self.sessions {
+            for event in session.take_events() {
+                let session_id = *session_id;
+                self.events.push_back(match event {
+                    SessionEvent::ParticipantJoined(participant_id) => {
+                        HostEvent::ParticipantJoined {
+                            session_id,
+                            participant_id,
+                        }
+                    }
+                    SessionEvent::ParticipantLeft(participant_id) => HostEvent::ParticipantLeft {
+                        session_id,
+                        participant_id,
+                    },
+                    SessionEvent::ForcedStep(tick_id, participant_id) => HostEvent::ForcedStep {
+                        session_id,
+                        participant_id,
+                        tick_id,
+                    },
+                });
+            }
+        }
+    }
+
+    /// Removes and returns the events that have happened since the last call, oldest first:
+    /// connections that are created, connected and destroyed, participants that join and leave,
+    /// game state downloads, forced steps, protocol errors and rate limit violations.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = HostEvent> + '_ {
+        self.report_session_events();
+        self.events.drain(..)
+    }
+
+    /// Creates the commands that the host should send to a connection on its own schedule.
+    ///
+    /// Pushes the authoritative steps that the connection has not acknowledged yet, and sends
+    /// or resends blob stream chunks for an ongoing game state download. This enables connections
+    /// without any participants, and connections that are idle, to keep receiving updates.
+    ///
+    /// Once this has been called for a connection, its pings are answered here instead of in
+    /// [`HostLogic::update`], with the time the ping waited for the schedule taken out of the pong.
+    ///
+    /// # Parameters
+    ///
+    /// - `connection_id`: The `HostConnectionId` of the connection to send to.
+    /// - `now`: The current absolute time in milliseconds precision.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of `HostToClientCommands` to be sent to the client,
+    /// or a `HostLogicError` if the commands could not be created.
+    ///
+    /// # Errors
+    ///
+    /// - `HostLogicError::UnknownConnectionId` if the connection is not found.
+    /// - `HostLogicError::UnknownSession` if the session of the connection is not found.
+    /// - `HostLogicError::BlobStreamErr` if the blob stream chunks could not be sent.
+    /// - `HostLogicError::IoError` if the authoritative steps could not be serialized.
+    pub fn send(
+        &mut self,
+        connection_id: HostConnectionId,
+        now: Millis,
+    ) -> Result<Vec<HostToClientCommands<Step<StepT>>>, HostLogicError> {
+        let connection = self
+            .connections
+            .get_mut(&connection_id.0)
+            .ok_or(HostLogicError::UnknownConnectionId(connection_id))?;
+
+        match connection.phase {
+            Phase::Connected => {
+                let session = self
+                    .sessions
+                    .get(&connection.session_id)
+                    .ok_or(HostLogicError::UnknownSession(connection.session_id))?;
+                connection.send(now, session)
+            }
+            Phase::WaitingForValidConnectRequest => Ok(vec![]),
+        }
     }
 }
@@ -0,0 +1,83 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use monotonic_time_rs::MillisDuration;
+use nimble_participant::ParticipantId;
+use nimble_step::Step;
+
+/// Decides what is produced for a participant that has not provided a step for a tick,
+/// and whether a tick waits for every participant before it is produced.
+///
+/// A step that arrives after its tick has been produced is always discarded.
+pub trait MissingStepPolicy<T> {
+    /// The step to produce for a participant that has no step for the tick.
+    ///
+    /// `last_custom_step` is the last `Step::Custom` that was produced for the participant, if any.
+    fn missing_step(
+        &mut self,
+        participant_id: ParticipantId,
+        last_custom_step: Option<&T>,
+    ) -> Step<T>;
+
+    /// How long past the deadline of a tick to wait for every participant before the tick is
+    /// produced with missing steps. Replaces the `forced_step_grace_period` of the `TickSettings`.
+    ///
+    /// `None` produces a tick as soon as any participant has provided its step for it.
+    fn lockstep_wait(&self) -> Option<MillisDuration> {
+        None
+    }
+}
+
+/// Produces `Step::Forced` for missing steps. Used until another [`MissingStepPolicy`] is set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ForcedStepPolicy;
+
+impl<T> MissingStepPolicy<T> for ForcedStepPolicy {
+    fn missing_step(&mut self, _: ParticipantId, _: Option<&T>) -> Step<T> {
+        Step::Forced
+    }
+}
+
+/// Repeats the last custom step of the participant, or `Step::Forced` if there is none yet.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RepeatLastCustomStepPolicy;
+
+impl<T: Clone> MissingStepPolicy<T> for RepeatLastCustomStepPolicy {
+    fn missing_step(&mut self, _: ParticipantId, last_custom_step: Option<&T>) -> Step<T> {
+        last_custom_step.map_or(Step::Forced, |step| Step::Custom(step.clone()))
+    }
+}
+
+/// Produces a game supplied custom step, e.g. "no input", for missing steps.
+#[derive(Debug, Clone)]
+pub struct DefaultStepPolicy<T>(pub T);
+
+impl<T: Clone> MissingStepPolicy<T> for DefaultStepPolicy<T> {
+    fn missing_step(&mut self, _: ParticipantId, _: Option<&T>) -> Step<T> {
+        Step::Custom(self.0.clone())
+    }
+}
+
+/// Waits for every participant, up to `max_wait` past the deadline of the tick, and then
+/// produces the missing steps with `missing_step_policy`.
+#[derive(Debug, Clone)]
+pub struct LockstepPolicy<P> {
+    pub max_wait: MillisDuration,
+    pub missing_step_policy: P,
+}
+
+impl<T, P: MissingStepPolicy<T>> MissingStepPolicy<T> for LockstepPolicy<P> {
+    fn missing_step(
+        &mut self,
+        participant_id: ParticipantId,
+        last_custom_step: Option<&T>,
+    ) -> Step<T> {
+        self.missing_step_policy
+            .missing_step(participant_id, last_custom_step)
+    }
+
+    fn lockstep_wait(&self) -> Option<MillisDuration> {
+        Some(self.max_wait)
+    }
+}
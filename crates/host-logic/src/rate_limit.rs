@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use monotonic_time_rs::{Millis, MillisDuration};
+use tick_id::TickId;
+
+/// Budgets for what a single connection can send to the host.
+///
+/// Datagrams and download requests over budget are ignored, and so are predicted steps
+/// too far ahead. Every violation is reported as a `HostEvent::RateLimitExceeded`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimits {
+    pub max_datagrams_per_second: u32,
+    pub max_commands_per_datagram: usize,
+    /// How many ticks ahead of the tick to produce that predicted steps are accepted for.
+    pub max_predicted_steps_ahead: u32,
+    pub max_download_requests_per_second: u32,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            max_datagrams_per_second: 256,
+            max_commands_per_datagram: 64,
+            max_predicted_steps_ahead: 128,
+            max_download_requests_per_second: 32,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RateLimitViolation {
+    /// Reported once for each second that the connection exceeds the budget.
+    DatagramsPerSecond,
+    CommandsPerDatagram(usize),
+    /// The tick of the first predicted step that was too far ahead.
+    PredictedStepsAhead(TickId),
+    /// Reported once for each second that the connection exceeds the budget.
+    DownloadRequestsPerSecond,
+}
+
+/// Counts occurrences within one second windows.
+#[derive(Debug, Default)]
+pub(crate) struct RateCounter {
+    window_started_at: Option<Millis>,
+    count: u32,
+}
+
+impl RateCounter {
+    const WINDOW: MillisDuration = MillisDuration::from_millis(1000);
+
+    /// Counts an occurrence and returns `true` if it is within `max_per_second`.
+    pub(crate) fn count(&mut self, now: Millis, max_per_second: u32) -> bool {
+        let is_new_window = self.window_started_at.is_none_or(|started_at| {
+            now.checked_duration_since_ms(started_at)
+                .is_some_and(|duration| duration >= Self::WINDOW)
+        });
+        if is_new_window {
+            self.window_started_at = Some(now);
+            self.count = 0;
+        }
+
+        self.count = self.count.saturating_add(1);
+        self.count <= max_per_second
+    }
+
+    /// Returns `true` if the last counted occurrence was the first one over `max_per_second`
+    /// in the current window.
+    pub(crate) const fn is_first_exceeded(&self, max_per_second: u32) -> bool {
+        self.count == max_per_second.saturating_add(1)
+    }
+}
+
+/// The rate counters and the not yet reported violations of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionRateLimiter {
+    pub(crate) datagrams: RateCounter,
+    pub(crate) download_requests: RateCounter,
+    pub(crate) violations: Vec<RateLimitViolation>,
+}
+
+impl ConnectionRateLimiter {
+    /// Counts an incoming datagram with `command_count` commands, and returns `true`
+    /// if it should be handled.
+    pub(crate) fn allow_datagram(
+        &mut self,
+        now: Millis,
+        command_count: usize,
+        rate_limits: &RateLimits,
+    ) -> bool {
+        let max_per_second = rate_limits.max_datagrams_per_second;
+        if !self.datagrams.count(now, max_per_second) {
+            if self.datagrams.is_first_exceeded(max_per_second) {
+                self.violations.push(RateLimitViolation::DatagramsPerSecond);
+            }
+            return false;
+        }
+
+        if command_count > rate_limits.max_commands_per_datagram {
+            self.violations
+                .push(RateLimitViolation::CommandsPerDatagram(command_count));
+            return false;
+        }
+
+        true
+    }
+
+    /// Counts a download request, and returns `true` if it should be handled.
+    pub(crate) fn allow_download_request(&mut self, now: Millis, rate_limits: &RateLimits) -> bool {
+        let max_per_second = rate_limits.max_download_requests_per_second;
+        if self.download_requests.count(now, max_per_second) {
+            return true;
+        }
+        if self.download_requests.is_first_exceeded(max_per_second) {
+            self.violations
+                .push(RateLimitViolation::DownloadRequestsPerSecond);
+        }
+        false
+    }
+}
@@ -3,25 +3,89 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 
+use crate::authenticator::ParticipantMetadata;
+use crate::bot::Bot;
 use crate::combine::HostCombinator;
+use crate::download_cache::DownloadCache;
+use crate::err::HostLogicError;
+use crate::event::SessionEvent;
+use crate::missing_step::MissingStepPolicy;
+use crate::tick_clock::{TickClock, TickSettings};
 use freelist_rs::FreeList;
+use monotonic_time_rs::{Millis, MillisDuration};
 use nimble_participant::ParticipantId;
+use nimble_protocol::SessionConnectionSecret;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use tick_id::TickId;
 
-#[derive(Copy, Clone, Debug)]
+/// How long a paused session waits after a resume request, by default.
+pub const DEFAULT_RESUME_DELAY: MillisDuration = MillisDuration::from_millis(128);
+
+#[derive(Clone, Debug)]
 pub struct Participant {
     pub id: ParticipantId,
     pub client_local_index: u8,
+    /// Set by the `Authenticator` when the participant joined.
+    pub metadata: ParticipantMetadata,
+    /// Set while the steps of the participant are provided by a [`Bot`] on the host.
+    pub is_bot: bool,
+    /// The number of predicted steps from the participant that the `StepValidator` rejected.
+    pub step_violations: u32,
+}
+
+/// The participants that joined through the same connection.
+///
+/// The party secret is handed out to the client, so it can get the same participants back
+/// over a new connection.
+#[derive(Debug, Clone)]
+pub struct Party {
+    pub id: u8,
+    pub secret: SessionConnectionSecret,
+    pub participant_ids: Vec<ParticipantId>,
+    /// The tick that was about to be produced when the party lost its connection.
+    pub disconnected_at: Option<TickId>,
+}
+
+/// Limits for how many participants that can join the session.
+///
+/// Join game requests that would exceed a limit are answered with a `JoinGameRejected`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParticipantLimits {
+    /// The maximum number of participants in the session.
+    pub max_participants: usize,
+
+    /// The maximum number of participants that can join through a single connection.
+    pub max_participants_per_connection: usize,
+
+    /// The maximum number of local players in a single join game request.
+    pub max_local_players_per_request: usize,
+}
+
+impl Default for ParticipantLimits {
+    fn default() -> Self {
+        Self {
+            max_participants: 0xff,
+            max_participants_per_connection: 8,
+            max_local_players_per_request: 8,
+        }
+    }
 }
 
 #[allow(clippy::module_name_repetitions)] // TODO: Rename GameSession or module
 pub struct GameSession<StepT: Clone + std::fmt::Display> {
     pub participants: HashMap<ParticipantId, Rc<RefCell<Participant>>>,
     pub participant_ids: FreeList<u8>,
+    pub parties: HashMap<u8, Party>,
+    pub party_ids: FreeList<u8>,
+    pub participant_limits: ParticipantLimits,
     pub(crate) combinator: HostCombinator<StepT>,
+    pub(crate) tick_clock: TickClock,
+    pub(crate) resume_delay: MillisDuration,
+    resume_at: Option<Millis>,
+    pub(crate) download_cache: DownloadCache,
+    pub(crate) events: Vec<SessionEvent>,
 }
 
 impl<StepT: Clone + std::fmt::Display> Default for GameSession<StepT> {
@@ -36,10 +100,96 @@ impl<StepT: Clone + std::fmt::Display> GameSession<StepT> {
         Self {
             participants: HashMap::new(),
             participant_ids: FreeList::new(0xff),
+            parties: HashMap::new(),
+            party_ids: FreeList::new(0xff),
+            participant_limits: ParticipantLimits::default(),
             combinator: HostCombinator::<StepT>::new(tick_id),
+            tick_clock: TickClock::new(TickSettings::default()),
+            resume_delay: DEFAULT_RESUME_DELAY,
+            resume_at: None,
+            download_cache: DownloadCache::default(),
+            events: vec![],
         }
     }
 
+    /// Sets the policy that decides the steps for participants that have not provided a step
+    /// in time. Until it is set, `Step::Forced` is produced for them.
+    pub fn set_missing_step_policy(&mut self, policy: impl MissingStepPolicy<StepT> + 'static) {
+        self.combinator.set_missing_step_policy(policy);
+    }
+
+    /// Stops producing authoritative steps for the session. The clients are told the tick it is
+    /// paused at, so they can stop predicting.
+    pub fn pause(&mut self) {
+        self.combinator.pause();
+        self.resume_at = None;
+    }
+
+    /// Continues producing authoritative steps from the tick where the session was paused,
+    /// once `resume_delay` has passed. The clients are told when it resumes, so every client
+    /// starts predicting from the paused tick at the same time.
+    pub fn resume(&mut self, now: Millis) {
+        if self.combinator.paused_at().is_none() || self.resume_at.is_some() {
+            return;
+        }
+        self.resume_at = Some(now + self.resume_delay);
+        self.resume_if_due(now);
+    }
+
+    /// Resumes the session if a resume is scheduled at `now` or earlier.
+    /// The tick clock restarts, so the time spent paused is not produced as forced steps.
+    pub(crate) fn resume_if_due(&mut self, now: Millis) {
+        if self.resume_at.is_some_and(|resume_at| now >= resume_at) {
+            self.resume_at = None;
+            self.combinator.resume();
+            self.tick_clock.restart();
+        }
+    }
+
+    /// Returns the tick that the session is paused at, or `None` if it is running.
+    #[must_use]
+    pub const fn paused_at(&self) -> Option<TickId> {
+        self.combinator.paused_at()
+    }
+
+    /// Returns the time that a paused session resumes at, or `None` if no resume is scheduled.
+    #[must_use]
+    pub const fn resume_at(&self) -> Option<Millis> {
+        self.resume_at
+    }
+
+    /// Returns the milliseconds left at `now` until the paused session resumes, saturated to
+    /// fit the game step response header, or `None` if no resume is scheduled.
+    pub(crate) fn resume_in_millis(&self, now: Millis) -> Option<u16> {
+        self.resume_at.map(|resume_at| {
+            resume_at.checked_duration_since_ms(now).map_or(0, |left| {
+                u16::try_from(left.as_millis()).unwrap_or(u16::MAX)
+            })
+        })
+    }
+
+    /// Returns the events since the last call, including the produced forced steps.
+    pub(crate) fn take_events(&mut self) -> Vec<SessionEvent> {
+        let mut events = std::mem::take(&mut self.events);
+        events.extend(
+            self.combinator
+                .take_forced_steps()
+                .into_iter()
+                .map(|(tick_id, participant_id)| SessionEvent::ForcedStep(tick_id, participant_id)),
+        );
+        events
+    }
+
+    /// Advances the tick clock and returns the first tick that should not be produced yet.
+    pub(crate) fn deadline_tick_id(&mut self, now: Millis) -> TickId {
+        let grace_period = self
+            .combinator
+            .lockstep_wait()
+            .unwrap_or(self.tick_clock.settings().forced_step_grace_period);
+        self.tick_clock
+            .deadline_tick_id(now, self.combinator.tick_id_to_produce(), grace_period)
+    }
+
     pub fn create_participants(
         &mut self,
         client_local_indices: &[u8],
@@ -54,14 +204,282 @@ impl<StepT: Clone + std::fmt::Display> GameSession<StepT> {
             let participant = Rc::new(RefCell::new(Participant {
                 client_local_index: client_local_indices[index],
                 id: participant_id,
+                metadata: ParticipantMetadata::default(),
+                is_bot: false,
+                step_violations: 0,
             }));
 
             participants.push(participant.clone());
 
             self.participants
                 .insert(participant_id, participant.clone());
+            self.events
+                .push(SessionEvent::ParticipantJoined(participant_id));
+        }
+
+        Some(participants)
+    }
+
+    /// Adds a participant without a connection that gets its steps from the bot.
+    ///
+    /// Bots count towards `max_participants`. When a join game request would exceed it,
+    /// the joining players take over the participants of bots instead, lowest id first.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the session is full or there are no free participant ids.
+    pub fn add_bot(
+        &mut self,
+        bot: impl Bot<StepT> + 'static,
+    ) -> Result<ParticipantId, HostLogicError> {
+        if self.participants.len() >= self.participant_limits.max_participants {
+            return Err(HostLogicError::SessionFull);
+        }
+
+        let participant = self
+            .create_participants(&[0])
+            .ok_or(HostLogicError::NoFreeParticipantIds)?
+            .remove(0);
+        let participant_id = {
+            let mut participant = participant.borrow_mut();
+            participant.is_bot = true;
+            participant.id
+        };
+
+        self.combinator.add_bot(participant_id, Box::new(bot));
+
+        Ok(participant_id)
+    }
+
+    /// Removes the bot and its participant from the session.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the participant is not a bot in the session.
+    pub fn remove_bot(&mut self, participant_id: ParticipantId) -> Result<(), HostLogicError> {
+        if !self.combinator.is_bot(participant_id) {
+            return Err(HostLogicError::UnknownBot(participant_id));
+        }
+
+        self.remove_participant(participant_id)?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn bot_count(&self) -> usize {
+        self.participants
+            .values()
+            .filter(|participant| participant.borrow().is_bot)
+            .count()
+    }
+
+    /// Hands over the participants of bots, lowest id first, to joining players with
+    /// the `client_local_indices`.
+    ///
+    /// Returns `None` if there are not enough bots.
+    pub(crate) fn replace_bots(
+        &mut self,
+        client_local_indices: &[u8],
+    ) -> Option<Vec<Rc<RefCell<Participant>>>> {
+        let mut bot_ids: Vec<_> = self
+            .participants
+            .values()
+            .filter(|participant| participant.borrow().is_bot)
+            .map(|participant| participant.borrow().id)
+            .collect();
+        if bot_ids.len() < client_local_indices.len() {
+            return None;
+        }
+        bot_ids.sort_by_key(|participant_id| participant_id.0);
+
+        let mut participants = vec![];
+        for (participant_id, client_local_index) in bot_ids.into_iter().zip(client_local_indices) {
+            self.combinator.replace_bot(participant_id);
+            let participant = self.participants.get(&participant_id)?.clone();
+            {
+                let mut participant = participant.borrow_mut();
+                participant.is_bot = false;
+                participant.client_local_index = *client_local_index;
+            }
+            participants.push(participant);
         }
 
         Some(participants)
     }
+
+    /// Removes the participant from the session, frees the participant id and
+    /// removes its incoming step buffer.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the participant is not in the session.
+    pub fn remove_participant(
+        &mut self,
+        participant_id: ParticipantId,
+    ) -> Result<Rc<RefCell<Participant>>, HostLogicError> {
+        let participant = self
+            .participants
+            .remove(&participant_id)
+            .ok_or(HostLogicError::UnknownPartyMember(participant_id))?;
+
+        self.participant_ids.free(participant_id.0).map_err(|err| {
+            HostLogicError::ParticipantFreeListError {
+                participant_id,
+                message: err,
+            }
+        })?;
+
+        self.combinator.remove_buffer(participant_id);
+        self.events
+            .push(SessionEvent::ParticipantLeft(participant_id));
+
+        for party in self.parties.values_mut() {
+            party.participant_ids.retain(|id| *id != participant_id);
+        }
+
+        Ok(participant)
+    }
+
+    /// Creates a new party with a random secret.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError::NoFreePartyIds` if there are no free party ids.
+    pub fn create_party(&mut self) -> Result<&mut Party, HostLogicError> {
+        let party_id = self
+            .party_ids
+            .allocate()
+            .ok_or(HostLogicError::NoFreePartyIds)?;
+        let party = Party {
+            id: party_id,
+            secret: SessionConnectionSecret {
+                value: rand::random(),
+            },
+            participant_ids: vec![],
+            disconnected_at: None,
+        };
+
+        Ok(self.parties.entry(party_id).or_insert(party))
+    }
+
+    #[must_use]
+    pub fn find_party_by_secret(&self, secret: SessionConnectionSecret) -> Option<&Party> {
+        self.parties.values().find(|party| party.secret == secret)
+    }
+
+    /// Keeps the participants of the party in the session, but produces
+    /// `Step::WaitingForReconnect` for them until the party is reconnected or removed.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the party is not in the session.
+    pub fn disconnect_party(&mut self, party_id: u8) -> Result<(), HostLogicError> {
+        let party = self
+            .parties
+            .get_mut(&party_id)
+            .ok_or(HostLogicError::UnknownParty(party_id))?;
+
+        party.disconnected_at = Some(self.combinator.tick_id_to_produce());
+        for participant_id in &party.participant_ids {
+            self.combinator.wait_for_reconnect(*participant_id);
+        }
+
+        Ok(())
+    }
+
+    /// Starts accepting steps for the participants of the party again.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the party is not in the session.
+    pub fn reconnect_party(
+        &mut self,
+        party_id: u8,
+    ) -> Result<Vec<Rc<RefCell<Participant>>>, HostLogicError> {
+        let party = self
+            .parties
+            .get_mut(&party_id)
+            .ok_or(HostLogicError::UnknownParty(party_id))?;
+
+        party.disconnected_at = None;
+        for participant_id in &party.participant_ids {
+            self.combinator.reconnect(*participant_id);
+        }
+
+        Ok(party
+            .participant_ids
+            .iter()
+            .filter_map(|participant_id| self.participants.get(participant_id).cloned())
+            .collect())
+    }
+
+    /// Removes the party and all of its participants from the session.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if the party is not in the session.
+    pub fn remove_party(&mut self, party_id: u8) -> Result<(), HostLogicError> {
+        let party = self
+            .parties
+            .remove(&party_id)
+            .ok_or(HostLogicError::UnknownParty(party_id))?;
+
+        self.party_ids
+            .free(party_id)
+            .map_err(|err| HostLogicError::PartyFreeListError {
+                party_id,
+                message: err,
+            })?;
+
+        for participant_id in party.participant_ids {
+            self.remove_participant(participant_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the parties that have waited for reconnect since `tick_id` or earlier.
+    ///
+    /// # Errors
+    ///
+    /// `HostLogicError` if a party could not be removed.
+    pub fn remove_parties_disconnected_since(
+        &mut self,
+        tick_id: TickId,
+    ) -> Result<(), HostLogicError> {
+        let timed_out_party_ids: Vec<u8> = self
+            .parties
+            .values()
+            .filter(|party| party.disconnected_at.is_some_and(|at| at <= tick_id))
+            .map(|party| party.id)
+            .collect();
+
+        for party_id in timed_out_party_ids {
+            self.remove_party(party_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the parties that have been waiting for reconnect longer than the reconnect timeout.
+    pub(crate) fn remove_timed_out_parties(
+        &mut self,
+        reconnect_timeout: MillisDuration,
+    ) -> Result<(), HostLogicError> {
+        let tick_duration = self.tick_clock.settings().tick_duration.as_millis().max(1);
+        let timeout_tick_count =
+            u32::try_from(reconnect_timeout.as_millis() / tick_duration).unwrap_or(u32::MAX);
+
+        if let Some(disconnected_since) = self
+            .combinator
+            .tick_id_to_produce()
+            .0
+            .checked_sub(timeout_tick_count)
+        {
+            self.remove_parties_disconnected_since(TickId(disconnected_since))?;
+        }
+
+        Ok(())
+    }
 }
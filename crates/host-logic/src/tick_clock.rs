@@ -0,0 +1,97 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use monotonic_time_rs::{Millis, MillisDuration};
+use tick_id::TickId;
+
+/// Settings for producing authoritative steps on a fixed clock.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TickSettings {
+    /// The fixed duration of each authoritative tick.
+    pub tick_duration: MillisDuration,
+
+    /// The maximum number of ticks that are caught up in a single call to `tick`.
+    pub max_ticks_per_update: u16,
+
+    /// How long past the deadline of a tick the host waits for late steps before
+    /// producing the tick with `Step::Forced` for the participants that are missing.
+    /// A lockstep `MissingStepPolicy` replaces it with its own wait.
+    pub forced_step_grace_period: MillisDuration,
+}
+
+impl Default for TickSettings {
+    fn default() -> Self {
+        Self {
+            tick_duration: MillisDuration::from_millis(16),
+            max_ticks_per_update: 4,
+            forced_step_grace_period: MillisDuration::from_millis(100),
+        }
+    }
+}
+
+/// Keeps track of which authoritative ticks have passed their deadline.
+///
+/// The clock starts at the first call to [`TickClock::deadline_tick_id`].
+#[derive(Debug)]
+pub(crate) struct TickClock {
+    settings: TickSettings,
+    consumed_absolute_time: Option<Millis>,
+    deadline_tick_id: TickId,
+}
+
+impl TickClock {
+    pub const fn new(settings: TickSettings) -> Self {
+        Self {
+            settings,
+            consumed_absolute_time: None,
+            deadline_tick_id: TickId(0),
+        }
+    }
+
+    pub const fn settings(&self) -> &TickSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: TickSettings) {
+        self.settings = settings;
+    }
+
+    /// Restarts the clock at the next call to [`TickClock::deadline_tick_id`], so the time
+    /// that has passed is not caught up, e.g. after the session has been paused.
+    pub fn restart(&mut self) {
+        self.consumed_absolute_time = None;
+    }
+
+    /// Advances the clock and returns the first tick that has not yet passed its deadline
+    /// (including the grace period). Every tick before it should be produced.
+    pub fn deadline_tick_id(
+        &mut self,
+        now: Millis,
+        tick_id_to_produce: TickId,
+        grace_period: MillisDuration,
+    ) -> TickId {
+        let Some(consumed_absolute_time) = self.consumed_absolute_time else {
+            self.consumed_absolute_time = Some(now);
+            self.deadline_tick_id = tick_id_to_produce;
+            return self.deadline_tick_id;
+        };
+
+        let Some(time_ahead) = now.checked_duration_since_ms(consumed_absolute_time) else {
+            return self.deadline_tick_id;
+        };
+
+        let tick_duration = self.settings.tick_duration.as_millis().max(1);
+        let time_ahead_of_grace_period = time_ahead
+            .as_millis()
+            .saturating_sub(grace_period.as_millis());
+        let tick_count = (time_ahead_of_grace_period / tick_duration)
+            .min(u64::from(self.settings.max_ticks_per_update));
+
+        self.consumed_absolute_time =
+            Some(consumed_absolute_time + MillisDuration::from_millis(tick_count * tick_duration));
+        self.deadline_tick_id += tick_count as u32;
+
+        self.deadline_tick_id
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+use nimble_participant::ParticipantId;
+use tick_id::TickId;
+
+/// What to do with a predicted step that a client sent for one of its participants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepValidation<T> {
+    /// The step is used as it is.
+    Accept,
+    /// The step is replaced, e.g. with values clamped to a valid range.
+    Sanitize(T),
+    /// The step is discarded and counted as a violation for the participant.
+    Reject,
+}
+
+/// Checks the predicted steps from clients before they are added to the combinator,
+/// so a malicious client can not inject steps that every peer then simulates.
+pub trait StepValidator<T> {
+    /// Called once for every predicted step that is about to be added for the participant.
+    fn validate(
+        &mut self,
+        participant_id: ParticipantId,
+        tick_id: TickId,
+        step: &T,
+    ) -> StepValidation<T>;
+}
+
+/// Accepts every step. Used until another [`StepValidator`] is set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AcceptAllStepValidator;
+
+impl<T> StepValidator<T> for AcceptAllStepValidator {
+    fn validate(&mut self, _: ParticipantId, _: TickId, _: &T) -> StepValidation<T> {
+        StepValidation::Accept
+    }
+}
+
+/// What happens to a participant that has reached the `max_violations`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepViolationAction {
+    /// Every further step from the participant is discarded, and the missing step policy
+    /// decides its steps.
+    Drop,
+    /// `Step::Forced` is produced for the participant from now on.
+    Force,
+    /// The participant is removed from the session, and a `Step::Left` is produced for it.
+    Kick,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepViolationSettings {
+    /// The number of rejected steps that a participant can have before the `action` is taken.
+    pub max_violations: u32,
+    pub action: StepViolationAction,
+}
+
+impl Default for StepViolationSettings {
+    fn default() -> Self {
+        Self {
+            max_violations: 10,
+            action: StepViolationAction::Force,
+        }
+    }
+}
@@ -2,9 +2,13 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
-use nimble_host_logic::combinator::Combinator;
-use nimble_participant::ParticipantId;
-use nimble_step::Step;
+use monotonic_time_rs::MillisDuration;
+use nimble_host_logic::combinator::{Combinator, CombinatorError};
+use nimble_host_logic::missing_step::{
+    DefaultStepPolicy, ForcedStepPolicy, LockstepPolicy, RepeatLastCustomStepPolicy,
+};
+use nimble_participant::{ParticipantId, HOST_PARTICIPANT_ID};
+use nimble_step::{JoinedData, Step};
 use std::fmt::{Display, Formatter};
 use tick_id::TickId;
 use tick_queue::Queue;
@@ -56,3 +60,168 @@ fn combinator_add() {
     let first_step = combined_step.get(&ParticipantId(2)); // Participant 1 has been popped up previously
     assert_eq!(first_step.unwrap(), &Step::Custom(TestStep::SelectTeam(42)));
 }
+
+#[test_log::test]
+fn combinator_announce() {
+    let mut combinator = Combinator::<TestStep>::new(TickId(10));
+    combinator.create_buffer(ParticipantId(1));
+    combinator.create_buffer(ParticipantId(2));
+    combinator.announce(
+        ParticipantId(2),
+        Step::Joined(JoinedData {
+            tick_id: TickId(10),
+        }),
+    );
+
+    combinator
+        .add(ParticipantId(1), TickId(10), TestStep::InGame(-2))
+        .expect("should be the expected tick");
+    combinator
+        .add(ParticipantId(2), TickId(10), TestStep::SelectTeam(42))
+        .expect("should be the expected tick");
+
+    let (produced_tick_id, combined_step) = combinator.produce().unwrap();
+
+    assert_eq!(produced_tick_id, TickId(10));
+    assert_eq!(combined_step.len(), 2);
+    assert_eq!(
+        combined_step.get(&ParticipantId(1)),
+        Some(&Step::Custom(TestStep::InGame(-2)))
+    );
+    assert_eq!(
+        combined_step.get(&ParticipantId(2)),
+        Some(&Step::Joined(JoinedData {
+            tick_id: TickId(10)
+        }))
+    );
+    assert!(combinator.announcements.is_empty());
+}
+
+#[test_log::test]
+fn combinator_missing_step_policy() {
+    let mut combinator = Combinator::<TestStep>::new(TickId(0));
+    combinator.set_missing_step_policy(RepeatLastCustomStepPolicy);
+    combinator.create_buffer(ParticipantId(1));
+    combinator.create_buffer(ParticipantId(2));
+
+    combinator
+        .add(ParticipantId(1), TickId(0), TestStep::InGame(-2))
+        .expect("should be the expected tick");
+    combinator
+        .add(ParticipantId(2), TickId(0), TestStep::SelectTeam(42))
+        .expect("should be the expected tick");
+    combinator.produce().unwrap();
+
+    combinator.create_buffer(ParticipantId(3));
+    combinator
+        .add(ParticipantId(1), TickId(1), TestStep::InGame(3))
+        .expect("should be the expected tick");
+    let (_, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(
+        combined_step.get(&ParticipantId(1)),
+        Some(&Step::Custom(TestStep::InGame(3)))
+    );
+    assert_eq!(
+        combined_step.get(&ParticipantId(2)),
+        Some(&Step::Custom(TestStep::SelectTeam(42)))
+    );
+    assert_eq!(combined_step.get(&ParticipantId(3)), Some(&Step::Forced));
+
+    combinator.set_missing_step_policy(DefaultStepPolicy(TestStep::InGame(0)));
+    let (produced_tick_id, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(produced_tick_id, TickId(2));
+    assert_eq!(combined_step.len(), 3);
+    assert!(combined_step
+        .values()
+        .all(|step| step == &Step::Custom(TestStep::InGame(0))));
+}
+
+#[test_log::test]
+fn combinator_lockstep_waits_for_every_participant() {
+    let mut combinator = Combinator::<TestStep>::new(TickId(0));
+    combinator.set_missing_step_policy(LockstepPolicy {
+        max_wait: MillisDuration::from_millis(100),
+        missing_step_policy: ForcedStepPolicy,
+    });
+    assert_eq!(
+        combinator.lockstep_wait(),
+        Some(MillisDuration::from_millis(100))
+    );
+    combinator.create_buffer(ParticipantId(1));
+    combinator.create_buffer(ParticipantId(2));
+
+    combinator
+        .add(ParticipantId(1), TickId(0), TestStep::InGame(-2))
+        .expect("should be the expected tick");
+    assert!(matches!(
+        combinator.produce(),
+        Err(CombinatorError::NotReadyToProduceStep {
+            can_provide: 1,
+            can_not_provide: 1
+        })
+    ));
+
+    let (produced_tick_id, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(produced_tick_id, TickId(0));
+    assert_eq!(
+        combined_step.get(&ParticipantId(1)),
+        Some(&Step::Custom(TestStep::InGame(-2)))
+    );
+    assert_eq!(combined_step.get(&ParticipantId(2)), Some(&Step::Forced));
+}
+
+#[test_log::test]
+fn combinator_host_steps() {
+    let mut combinator = Combinator::<TestStep>::new(TickId(10));
+    combinator.create_buffer(ParticipantId(1));
+
+    assert!(matches!(
+        combinator.push_host_step(TickId(9), TestStep::SelectTeam(1)),
+        Err(CombinatorError::HostStepTickAlreadyProduced(TickId(9)))
+    ));
+    combinator
+        .push_host_step(TickId(11), TestStep::SelectTeam(2))
+        .expect("tick should not be produced yet");
+    assert!(matches!(
+        combinator.push_host_step(TickId(11), TestStep::SelectTeam(3)),
+        Err(CombinatorError::HostStepAlreadyExists(TickId(11)))
+    ));
+
+    let (_, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(combined_step.get(&HOST_PARTICIPANT_ID), None);
+
+    let (produced_tick_id, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(produced_tick_id, TickId(11));
+    assert_eq!(
+        combined_step.get(&HOST_PARTICIPANT_ID),
+        Some(&Step::Custom(TestStep::SelectTeam(2)))
+    );
+    assert_eq!(combined_step.get(&ParticipantId(1)), Some(&Step::Forced));
+    assert!(combinator.host_steps.is_empty());
+}
+
+#[test_log::test]
+fn combinator_forced_participants() {
+    let mut combinator = Combinator::<TestStep>::new(TickId(0));
+    combinator.set_missing_step_policy(DefaultStepPolicy(TestStep::InGame(0)));
+    combinator.create_buffer(ParticipantId(1));
+    combinator.create_buffer(ParticipantId(2));
+
+    combinator.force_steps(ParticipantId(1));
+    assert!(combinator.get_mut(&ParticipantId(1)).is_none());
+
+    combinator.wait_for_reconnect(ParticipantId(1));
+    assert!(combinator.reconnect(ParticipantId(1)));
+    assert!(combinator.get_mut(&ParticipantId(1)).is_none());
+
+    let (_, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(combined_step.get(&ParticipantId(1)), Some(&Step::Forced));
+    assert_eq!(
+        combined_step.get(&ParticipantId(2)),
+        Some(&Step::Custom(TestStep::InGame(0)))
+    );
+
+    combinator.remove_buffer(ParticipantId(1));
+    let (_, combined_step) = combinator.produce_forced().unwrap();
+    assert_eq!(combined_step.get(&ParticipantId(1)), None);
+}
@@ -2,17 +2,50 @@
  * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/nimble-rust/nimble
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
-use crate::test_types::TestStateProvider;
+use crate::test_types::{
+    CountingStateProvider, TestSessionStateProvider, TestStateProvider, TestTicketAuthenticator,
+};
 use app_version::Version;
 use log::debug;
-use monotonic_time_rs::Millis;
+use monotonic_time_rs::{Millis, MillisDuration};
 use nimble_blob_stream::in_logic_front::FrontLogic;
-use nimble_blob_stream::prelude::{ReceiverToSenderFrontCommands, SenderToReceiverFrontCommands};
-use nimble_host_logic::HostLogic;
-use nimble_protocol::client_to_host::{ConnectRequest, DownloadGameStateRequest};
-use nimble_protocol::prelude::{ClientToHostCommands, HostToClientCommands};
-use nimble_protocol::ClientRequestId;
+use nimble_blob_stream::prelude::{
+    BlobStreamOutSettings, ReceiverToSenderFrontCommands, ResendTiming,
+    SenderToReceiverFrontCommands,
+};
+use nimble_host_logic::authenticator::ParticipantMetadata;
+use nimble_host_logic::bot::Bot;
+use nimble_host_logic::combinator::CombinatorError;
+use nimble_host_logic::download_cache::DownloadCacheSettings;
+use nimble_host_logic::err::HostLogicError;
+use nimble_host_logic::event::HostEvent;
+use nimble_host_logic::missing_step::{LockstepPolicy, RepeatLastCustomStepPolicy};
+use nimble_host_logic::rate_limit::{RateLimitViolation, RateLimits};
+use nimble_host_logic::session::{GameSession, ParticipantLimits};
+use nimble_host_logic::tick_clock::TickSettings;
+use nimble_host_logic::validator::{
+    StepValidation, StepValidator, StepViolationAction, StepViolationSettings,
+};
+use nimble_host_logic::HostConnectionId;
+use nimble_host_logic::{HostLogic, Phase, DEFAULT_SESSION_ID};
+use nimble_participant::{ParticipantId, HOST_PARTICIPANT_ID};
+use nimble_protocol::client_to_host::{
+    ConnectRequest, DownloadGameStateRequest, JoinGameType, JoinPlayerRequest, JoinPlayerRequests,
+    LeaveGameRequest, SessionPauseRequest, StepsAck, StepsRequest,
+};
+use nimble_protocol::host_to_client::{
+    ConnectionRejectedReason, JoinGameAccepted, JoinGameRejectedReason,
+};
+use nimble_protocol::prelude::{
+    ClientToHostCommands, CombinedSteps, HostToClientCommands, JoinGameRequest,
+};
+use nimble_protocol::{
+    ClientRequestId, SessionConnectionSecret, SessionId, NIMBLE_PROTOCOL_VERSION,
+};
 use nimble_sample_step::SampleStep;
+use nimble_step::{JoinedData, Step};
+use nimble_step_map::StepMap;
+use std::time::Duration;
 use tick_id::TickId;
 
 mod test_types;
@@ -33,11 +66,7 @@ fn game_state_download() {
     let now = Millis::from(0);
 
     let connect_request = ConnectRequest {
-        nimble_version: nimble_protocol::Version {
-            major: 0,
-            minor: 0,
-            patch: 0,
-        },
+        nimble_version: NIMBLE_PROTOCOL_VERSION,
         use_debug_stream: false,
         application_version: nimble_protocol::Version {
             major: version.major(),
@@ -45,6 +74,8 @@ fn game_state_download() {
             patch: version.patch(),
         },
         client_request_id: ClientRequestId(0),
+        session_id: DEFAULT_SESSION_ID,
+        client_token: vec![],
     };
 
     host.update(
@@ -186,3 +217,2083 @@ fn game_state_download() {
     host.destroy_connection(connection_id)
         .expect("Should destroy connection");
 }
+
+fn connect_request(version: Version) -> ConnectRequest {
+    ConnectRequest {
+        nimble_version: NIMBLE_PROTOCOL_VERSION,
+        use_debug_stream: false,
+        application_version: nimble_protocol::Version {
+            major: version.major(),
+            minor: version.minor(),
+            patch: version.patch(),
+        },
+        client_request_id: ClientRequestId(0),
+        session_id: DEFAULT_SESSION_ID,
+        client_token: vec![],
+    }
+}
+
+fn request_connect(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    connect_request: ConnectRequest,
+    state: &TestStateProvider,
+) -> Vec<HostToClientCommands<Step<SampleStep>>> {
+    host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::ConnectType(connect_request),
+        state,
+    )
+    .expect("it should work")
+}
+
+fn connect(
+    host: &mut HostLogic<SampleStep>,
+    version: Version,
+    state: &TestStateProvider,
+) -> HostConnectionId {
+    let connection_id = host.create_connection().expect("it should work");
+    request_connect(host, connection_id, connect_request(version), state);
+
+    connection_id
+}
+
+fn request_join(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    join_game_type: JoinGameType,
+    local_indices: &[u8],
+    state: &TestStateProvider,
+) -> Result<JoinGameAccepted, JoinGameRejectedReason> {
+    let join_request = JoinGameRequest {
+        client_request_id: ClientRequestId(0),
+        join_game_type,
+        player_requests: JoinPlayerRequests {
+            players: local_indices
+                .iter()
+                .map(|local_index| JoinPlayerRequest {
+                    local_index: *local_index,
+                })
+                .collect(),
+        },
+    };
+
+    let mut answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::JoinGameType(join_request),
+            state,
+        )
+        .expect("join request should be handled");
+
+    match answers.remove(0) {
+        HostToClientCommands::JoinGame(join_accepted) => Ok(join_accepted),
+        HostToClientCommands::JoinGameRejected(join_rejected) => {
+            assert_eq!(join_rejected.client_request_id, ClientRequestId(0));
+            Err(join_rejected.reason)
+        }
+        _ => panic!("expected join game accepted or rejected"),
+    }
+}
+
+fn join(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    local_indices: &[u8],
+    state: &TestStateProvider,
+) -> Vec<ParticipantId> {
+    request_join(
+        host,
+        connection_id,
+        JoinGameType::NoSecret,
+        local_indices,
+        state,
+    )
+    .expect("join should work")
+    .participants
+    .0
+    .iter()
+    .map(|participant| participant.participant_id)
+    .collect()
+}
+
+fn send_step(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    participant_id: ParticipantId,
+    tick_id: TickId,
+    state: &TestStateProvider,
+) -> Vec<StepMap<Step<SampleStep>>> {
+    send_steps(
+        host,
+        connection_id,
+        participant_id,
+        tick_id,
+        &[SampleStep::Jump],
+        state,
+    )
+}
+
+fn send_steps(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    participant_id: ParticipantId,
+    tick_id: TickId,
+    steps: &[SampleStep],
+    state: &TestStateProvider,
+) -> Vec<StepMap<Step<SampleStep>>> {
+    let predicted_steps = steps
+        .iter()
+        .map(|step| {
+            let mut predicted_step = StepMap::new();
+            predicted_step
+                .insert(participant_id, step.clone())
+                .expect("should be unique");
+            predicted_step
+        })
+        .collect();
+
+    let steps_request = StepsRequest {
+        ack: StepsAck {
+            waiting_for_tick_id: TickId(0),
+        },
+        combined_predicted_steps: CombinedSteps {
+            tick_id,
+            steps: predicted_steps,
+        },
+    };
+
+    let answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::Steps(steps_request),
+            state,
+        )
+        .expect("steps should be accepted");
+    host.post_update().expect("post update should work");
+
+    let HostToClientCommands::GameStep(game_step) = &answers[0] else {
+        panic!("expected game step response");
+    };
+
+    game_step
+        .authoritative_steps
+        .ranges
+        .iter()
+        .flat_map(|range| range.steps.clone())
+        .collect()
+}
+
+#[test_log::test]
+fn leave_game() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    let connection_id = connect(&mut host, version, &state);
+
+    let participant_ids = join(&mut host, connection_id, &[0, 1], &state);
+    assert_eq!(participant_ids.len(), 2);
+    assert_eq!(host.session().participants.len(), 2);
+
+    let answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::LeaveGameType(LeaveGameRequest {
+                client_request_id: ClientRequestId(0),
+                local_indices: vec![1],
+            }),
+            &state,
+        )
+        .expect("leave should work");
+
+    let HostToClientCommands::LeaveGame(leave_accepted) = &answers[0] else {
+        panic!("expected leave game accepted");
+    };
+    assert_eq!(leave_accepted.participants.0.len(), 1);
+    assert_eq!(leave_accepted.participants.0[0].local_index, 1);
+    let left_participant_id = leave_accepted.participants.0[0].participant_id;
+    assert_eq!(left_participant_id, participant_ids[1]);
+    assert_eq!(host.session().participants.len(), 1);
+
+    let remaining_participant_id = participant_ids[0];
+    send_step(
+        &mut host,
+        connection_id,
+        remaining_participant_id,
+        TickId(0),
+        &state,
+    );
+    let authoritative_steps = send_step(
+        &mut host,
+        connection_id,
+        remaining_participant_id,
+        TickId(1),
+        &state,
+    );
+
+    // Leaving overrides the pending join announcement for the same tick
+    let first_step = &authoritative_steps[0];
+    assert_eq!(first_step.get(&left_participant_id), Some(&Step::Left));
+    assert_eq!(
+        first_step.get(&remaining_participant_id),
+        Some(&Step::Joined(JoinedData { tick_id: TickId(0) }))
+    );
+
+    // The participant id should be free to be used again
+    let rejoined_ids = join(&mut host, connection_id, &[1], &state);
+    assert_eq!(rejoined_ids.len(), 1);
+    assert_eq!(host.session().participants.len(), 2);
+}
+
+#[test_log::test]
+fn leave_game_with_unknown_local_index() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    let connection_id = connect(&mut host, version, &state);
+    join(&mut host, connection_id, &[0], &state);
+
+    let result = host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::LeaveGameType(LeaveGameRequest {
+            client_request_id: ClientRequestId(0),
+            local_indices: vec![3],
+        }),
+        &state,
+    );
+
+    assert!(matches!(
+        result,
+        Err(nimble_host_logic::err::HostLogicError::UnknownLocalIndex(3))
+    ));
+    assert_eq!(host.session().participants.len(), 1);
+}
+
+#[test_log::test]
+fn destroy_connection_keeps_participants_until_reconnect_timeout() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_reconnect_timeout(MillisDuration::from_millis(16 * 3));
+
+    let staying_connection_id = connect(&mut host, version, &state);
+    let staying_participant_id = join(&mut host, staying_connection_id, &[0], &state)[0];
+
+    let leaving_connection_id = connect(&mut host, version, &state);
+    let leaving_participant_ids = join(&mut host, leaving_connection_id, &[0, 1], &state);
+    assert_eq!(host.session().participants.len(), 3);
+
+    host.destroy_connection(leaving_connection_id)
+        .expect("should destroy connection");
+    assert_eq!(host.session().participants.len(), 3);
+    assert!(host.get(leaving_connection_id).is_none());
+
+    for tick_id in 0..3 {
+        send_step(
+            &mut host,
+            staying_connection_id,
+            staying_participant_id,
+            TickId(tick_id),
+            &state,
+        );
+    }
+    // The tick after the timeout removes the participants
+    host.tick(Millis::from(0)).expect("tick should work");
+    assert_eq!(host.session().participants.len(), 1);
+
+    send_step(
+        &mut host,
+        staying_connection_id,
+        staying_participant_id,
+        TickId(3),
+        &state,
+    );
+    let authoritative_steps = send_step(
+        &mut host,
+        staying_connection_id,
+        staying_participant_id,
+        TickId(4),
+        &state,
+    );
+    assert_eq!(authoritative_steps.len(), 4);
+    for leaving_participant_id in &leaving_participant_ids {
+        assert_eq!(
+            authoritative_steps[1].get(leaving_participant_id),
+            Some(&Step::WaitingForReconnect)
+        );
+        assert_eq!(
+            authoritative_steps[2].get(leaving_participant_id),
+            Some(&Step::WaitingForReconnect)
+        );
+        assert_eq!(
+            authoritative_steps[3].get(leaving_participant_id),
+            Some(&Step::Left)
+        );
+    }
+
+    // The participant ids should be released
+    let new_connection_id = connect(&mut host, version, &state);
+    assert_eq!(join(&mut host, new_connection_id, &[0, 1], &state).len(), 2);
+    assert_eq!(host.session().participants.len(), 3);
+}
+
+#[test_log::test]
+fn reconnect_with_session_secret() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let other_connection_id = connect(&mut host, version, &state);
+    let other_join_accepted = request_join(
+        &mut host,
+        other_connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+
+    let connection_id = connect(&mut host, version, &state);
+    let join_accepted = request_join(
+        &mut host,
+        connection_id,
+        JoinGameType::NoSecret,
+        &[0, 1],
+        &state,
+    )
+    .expect("join should work");
+    assert_ne!(
+        join_accepted.party_and_session_secret,
+        other_join_accepted.party_and_session_secret
+    );
+
+    host.destroy_connection(connection_id)
+        .expect("should destroy connection");
+
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(16 * 2)).expect("tick should work");
+
+    let unknown_secret = SessionConnectionSecret {
+        value: join_accepted.party_and_session_secret.session_secret.value ^ 1,
+    };
+    let new_connection_id = connect(&mut host, version, &state);
+    assert!(matches!(
+        request_join(
+            &mut host,
+            new_connection_id,
+            JoinGameType::UseSessionSecret(unknown_secret),
+            &[0, 1],
+            &state,
+        ),
+        Err(JoinGameRejectedReason::UnknownSessionSecret)
+    ));
+
+    let rejoin_accepted = request_join(
+        &mut host,
+        new_connection_id,
+        JoinGameType::UseSessionSecret(join_accepted.party_and_session_secret.session_secret),
+        &[0, 1],
+        &state,
+    )
+    .expect("rejoin should work");
+    assert_eq!(
+        rejoin_accepted.party_and_session_secret,
+        join_accepted.party_and_session_secret
+    );
+    let participant_ids = |accepted: &JoinGameAccepted| -> Vec<(u8, ParticipantId)> {
+        accepted
+            .participants
+            .0
+            .iter()
+            .map(|participant| (participant.local_index, participant.participant_id))
+            .collect()
+    };
+    assert_eq!(
+        participant_ids(&rejoin_accepted),
+        participant_ids(&join_accepted)
+    );
+    assert_eq!(host.session().participants.len(), 3);
+
+    let participant_id = join_accepted.participants.0[0].participant_id;
+    let authoritative_steps = send_step(
+        &mut host,
+        new_connection_id,
+        participant_id,
+        TickId(2),
+        &state,
+    );
+    assert_eq!(authoritative_steps.len(), 2);
+    assert_eq!(
+        authoritative_steps[1].get(&participant_id),
+        Some(&Step::WaitingForReconnect)
+    );
+
+    send_step(
+        &mut host,
+        other_connection_id,
+        other_join_accepted.participants.0[0].participant_id,
+        TickId(2),
+        &state,
+    );
+    let authoritative_steps = send_step(
+        &mut host,
+        new_connection_id,
+        participant_id,
+        TickId(3),
+        &state,
+    );
+    assert_eq!(
+        authoritative_steps[2].get(&participant_id),
+        Some(&Step::Custom(SampleStep::Jump))
+    );
+}
+
+#[test_log::test]
+fn rejoin_with_secret_releases_party_and_checks_metadata() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_authenticator(TestTicketAuthenticator { max_local_index: 2 });
+
+    let connect_with_token = |host: &mut HostLogic<SampleStep>, client_token: &[u8]| {
+        let connection_id = host.create_connection().expect("it should work");
+        request_connect(
+            host,
+            connection_id,
+            ConnectRequest {
+                client_token: client_token.to_vec(),
+                ..connect_request(version)
+            },
+            &state,
+        );
+        connection_id
+    };
+
+    let connection_id = connect_with_token(&mut host, b"ticket:a");
+    let join_accepted = request_join(
+        &mut host,
+        connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+    let secret = join_accepted.party_and_session_secret.session_secret;
+    let party_id = join_accepted.party_and_session_secret.party_id;
+
+    // Another user can not take over the party with the secret
+    let other_user_connection_id = connect_with_token(&mut host, b"ticket:b");
+    assert!(matches!(
+        request_join(
+            &mut host,
+            other_user_connection_id,
+            JoinGameType::UseSessionSecret(secret),
+            &[0],
+            &state,
+        ),
+        Err(JoinGameRejectedReason::Unauthorized)
+    ));
+    assert_eq!(
+        host.get(connection_id).expect("connection").party_id,
+        Some(party_id)
+    );
+
+    // The same user rejoining from a connection that already has a party lets go of that party
+    let new_connection_id = connect_with_token(&mut host, b"ticket:a");
+    let previous_join_accepted = request_join(
+        &mut host,
+        new_connection_id,
+        JoinGameType::NoSecret,
+        &[1],
+        &state,
+    )
+    .expect("join should work");
+    let previous_party_id = previous_join_accepted.party_and_session_secret.party_id;
+
+    request_join(
+        &mut host,
+        new_connection_id,
+        JoinGameType::UseSessionSecret(secret),
+        &[0],
+        &state,
+    )
+    .expect("rejoin should work");
+
+    let new_connection = host.get(new_connection_id).expect("connection");
+    assert_eq!(new_connection.party_id, Some(party_id));
+    assert_eq!(
+        new_connection
+            .participant_lookup
+            .keys()
+            .copied()
+            .collect::<Vec<_>>(),
+        [join_accepted.participants.0[0].participant_id]
+    );
+    assert!(host.session().parties[&previous_party_id]
+        .disconnected_at
+        .is_some());
+    assert_eq!(host.get(connection_id).expect("connection").party_id, None);
+}
+
+#[test_log::test]
+fn tick_produces_forced_steps_after_grace_period() {
+    const TICK_ID: TickId = TickId(42);
+    let state = TestStateProvider {
+        tick_id: TICK_ID,
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TICK_ID, version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(50),
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    host.tick(Millis::from(1000))
+        .expect("should start the clock");
+    // Deadline for the first tick has passed, but not the grace period
+    host.tick(Millis::from(1000 + 16 + 49))
+        .expect("tick should work");
+    assert!(send_step(&mut host, connection_id, participant_id, TickId(0), &state).is_empty());
+
+    host.tick(Millis::from(1000 + 16 * 2 + 50))
+        .expect("tick should work");
+    let authoritative_steps =
+        send_step(&mut host, connection_id, participant_id, TickId(0), &state);
+    assert_eq!(authoritative_steps.len(), 2);
+    assert_eq!(
+        authoritative_steps[0].get(&participant_id),
+        Some(&Step::Joined(JoinedData { tick_id: TICK_ID }))
+    );
+    assert_eq!(
+        authoritative_steps[1].get(&participant_id),
+        Some(&Step::Forced)
+    );
+
+    // Only a limited number of ticks are caught up each call
+    host.tick(Millis::from(10_000)).expect("tick should work");
+    let authoritative_steps =
+        send_step(&mut host, connection_id, participant_id, TickId(0), &state);
+    assert_eq!(authoritative_steps.len(), 2 + 4);
+}
+
+#[test_log::test]
+fn lockstep_policy_waits_for_every_participant() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+    host.set_missing_step_policy(
+        DEFAULT_SESSION_ID,
+        LockstepPolicy {
+            max_wait: MillisDuration::from_millis(50),
+            missing_step_policy: RepeatLastCustomStepPolicy,
+        },
+    )
+    .expect("default session should exist");
+    assert!(matches!(
+        host.set_missing_step_policy(SessionId(1), RepeatLastCustomStepPolicy),
+        Err(HostLogicError::UnknownSession(SessionId(1)))
+    ));
+
+    let first_connection_id = connect(&mut host, version, &state);
+    let first_participant_id = join(&mut host, first_connection_id, &[0], &state)[0];
+    let second_connection_id = connect(&mut host, version, &state);
+    let second_participant_id = join(&mut host, second_connection_id, &[0], &state)[0];
+
+    host.tick(Millis::from(1000))
+        .expect("should start the clock");
+
+    for tick_id in [TickId(0), TickId(1)] {
+        assert_eq!(
+            send_step(
+                &mut host,
+                first_connection_id,
+                first_participant_id,
+                tick_id,
+                &state
+            )
+            .len(),
+            tick_id.0 as usize
+        );
+        assert_eq!(
+            send_step(
+                &mut host,
+                second_connection_id,
+                second_participant_id,
+                tick_id,
+                &state
+            )
+            .len(),
+            tick_id.0 as usize
+        );
+    }
+
+    assert_eq!(
+        send_step(
+            &mut host,
+            first_connection_id,
+            first_participant_id,
+            TickId(2),
+            &state
+        )
+        .len(),
+        2
+    );
+
+    // The deadline for the third tick has passed, but not the lockstep wait
+    host.tick(Millis::from(1000 + 16 * 3 + 49))
+        .expect("tick should work");
+    assert_eq!(
+        send_step(
+            &mut host,
+            first_connection_id,
+            first_participant_id,
+            TickId(3),
+            &state
+        )
+        .len(),
+        2
+    );
+
+    host.tick(Millis::from(1000 + 16 * 3 + 50))
+        .expect("tick should work");
+    let authoritative_steps = send_step(
+        &mut host,
+        first_connection_id,
+        first_participant_id,
+        TickId(4),
+        &state,
+    );
+    assert_eq!(authoritative_steps.len(), 3);
+    assert_eq!(
+        authoritative_steps[2].get(&first_participant_id),
+        Some(&Step::Custom(SampleStep::Jump))
+    );
+    assert_eq!(
+        authoritative_steps[2].get(&second_participant_id),
+        Some(&Step::Custom(SampleStep::Jump))
+    );
+}
+
+#[test_log::test]
+fn paused_session_resumes_from_the_paused_tick() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    host.tick(Millis::from(1000))
+        .expect("should start the clock");
+    host.tick(Millis::from(1000 + 16 * 2))
+        .expect("tick should work");
+    assert_eq!(host.session().paused_at(), None);
+
+    host.pause_session(DEFAULT_SESSION_ID)
+        .expect("default session should exist");
+    assert_eq!(host.session().paused_at(), Some(TickId(2)));
+
+    host.tick(Millis::from(10_000)).expect("tick should work");
+    assert_eq!(
+        send_step(&mut host, connection_id, participant_id, TickId(0), &state).len(),
+        2
+    );
+
+    let request_session_pause = |host: &mut HostLogic<SampleStep>, paused: bool| {
+        host.update(
+            connection_id,
+            Millis::from(10_000),
+            &ClientToHostCommands::SessionPause(SessionPauseRequest { paused }),
+            &state,
+        )
+    };
+    assert!(matches!(
+        request_session_pause(&mut host, false),
+        Err(HostLogicError::ClientPauseNotAllowed)
+    ));
+    assert_eq!(host.session().paused_at(), Some(TickId(2)));
+
+    host.set_clients_can_pause(true);
+    host.set_resume_delay(MillisDuration::from_millis(32));
+    let answers = request_session_pause(&mut host, false).expect("pause request should work");
+    let HostToClientCommands::GameStep(game_step) = &answers[0] else {
+        panic!("expected game step response");
+    };
+    assert_eq!(game_step.response_header.session_paused_at, Some(TickId(2)));
+    assert_eq!(game_step.response_header.session_resume_in_millis, Some(32));
+
+    // The session stays paused until the announced resume time
+    host.tick(Millis::from(10_000 + 16))
+        .expect("tick should work");
+    assert_eq!(host.session().paused_at(), Some(TickId(2)));
+    assert_eq!(host.session().resume_at(), Some(Millis::from(10_000 + 32)));
+    assert_eq!(
+        send_step(&mut host, connection_id, participant_id, TickId(1), &state).len(),
+        2
+    );
+
+    // The clock restarts, so the time spent paused is not caught up
+    host.tick(Millis::from(10_000 + 32))
+        .expect("tick should work");
+    assert_eq!(host.session().paused_at(), None);
+    assert_eq!(host.session().resume_at(), None);
+    assert_eq!(
+        send_step(&mut host, connection_id, participant_id, TickId(1), &state).len(),
+        2
+    );
+
+    host.tick(Millis::from(10_000 + 32 + 16))
+        .expect("tick should work");
+    let authoritative_steps =
+        send_step(&mut host, connection_id, participant_id, TickId(1), &state);
+    assert_eq!(authoritative_steps.len(), 3);
+}
+
+#[test_log::test]
+fn host_steps_are_included_in_authoritative_steps() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    host.push_host_step(DEFAULT_SESSION_ID, TickId(1), SampleStep::MoveLeft(3))
+        .expect("tick 1 should not be produced yet");
+    assert!(matches!(
+        host.push_host_step(SessionId(1), TickId(1), SampleStep::Nothing),
+        Err(HostLogicError::UnknownSession(SessionId(1)))
+    ));
+
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(16 * 2)).expect("tick should work");
+
+    assert!(matches!(
+        host.push_host_step(DEFAULT_SESSION_ID, TickId(1), SampleStep::Nothing),
+        Err(HostLogicError::CombinatorError(
+            CombinatorError::HostStepTickAlreadyProduced(TickId(1))
+        ))
+    ));
+
+    let authoritative_steps =
+        send_step(&mut host, connection_id, participant_id, TickId(0), &state);
+    assert_eq!(authoritative_steps.len(), 2);
+    assert_eq!(authoritative_steps[0].get(&HOST_PARTICIPANT_ID), None);
+    assert_eq!(
+        authoritative_steps[1].get(&HOST_PARTICIPANT_ID),
+        Some(&Step::Custom(SampleStep::MoveLeft(3)))
+    );
+    assert_eq!(
+        authoritative_steps[1].get(&participant_id),
+        Some(&Step::Forced)
+    );
+}
+
+struct JumpingBot;
+
+impl Bot<SampleStep> for JumpingBot {
+    fn step(&mut self, _: ParticipantId, _: TickId) -> SampleStep {
+        SampleStep::Jump
+    }
+}
+
+#[test_log::test]
+fn bots_provide_steps_until_replaced_by_joining_players() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+    host.set_participant_limits(ParticipantLimits {
+        max_participants: 2,
+        ..ParticipantLimits::default()
+    });
+
+    let first_bot_id = host
+        .add_bot(DEFAULT_SESSION_ID, JumpingBot)
+        .expect("should have room for a bot");
+    let second_bot_id = host
+        .add_bot(DEFAULT_SESSION_ID, JumpingBot)
+        .expect("should have room for a bot");
+    assert!(matches!(
+        host.add_bot(DEFAULT_SESSION_ID, JumpingBot),
+        Err(HostLogicError::SessionFull)
+    ));
+
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(16 * 2)).expect("tick should work");
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+    assert_eq!(participant_id, first_bot_id);
+    assert!(!host.session().participants[&participant_id].borrow().is_bot);
+
+    let authoritative_steps =
+        send_step(&mut host, connection_id, participant_id, TickId(2), &state);
+    assert!(authoritative_steps.len() >= 2);
+    assert_eq!(
+        authoritative_steps[0].get(&second_bot_id),
+        Some(&Step::Joined(JoinedData { tick_id: TickId(0) }))
+    );
+    assert_eq!(
+        authoritative_steps[1].get(&first_bot_id),
+        Some(&Step::Custom(SampleStep::Jump))
+    );
+    assert_eq!(
+        authoritative_steps[1].get(&second_bot_id),
+        Some(&Step::Custom(SampleStep::Jump))
+    );
+
+    assert!(matches!(
+        host.remove_bot(DEFAULT_SESSION_ID, participant_id),
+        Err(HostLogicError::UnknownBot(id)) if id == participant_id
+    ));
+    host.remove_bot(DEFAULT_SESSION_ID, second_bot_id)
+        .expect("should remove the bot");
+    assert_eq!(host.session().participants.len(), 1);
+}
+
+#[test_log::test]
+fn join_without_free_participant_ids_leaves_nothing_behind() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_participant_limits(ParticipantLimits {
+        max_participants: 0x100,
+        ..ParticipantLimits::default()
+    });
+
+    let bot_ids: Vec<_> = (0..0xff)
+        .map(|_| {
+            host.add_bot(DEFAULT_SESSION_ID, JumpingBot)
+                .expect("should have room for a bot")
+        })
+        .collect();
+
+    let connection_id = connect(&mut host, version, &state);
+    assert_join_rejected(
+        &mut host,
+        connection_id,
+        &[0],
+        &state,
+        JoinGameRejectedReason::SessionFull,
+    );
+    assert!(host.session().parties.is_empty());
+    assert_eq!(host.session().participants.len(), 0xff);
+    assert_eq!(host.session().bot_count(), 0xff);
+
+    host.remove_bot(DEFAULT_SESSION_ID, bot_ids[0])
+        .expect("should remove the bot");
+    let participant_ids = join(&mut host, connection_id, &[0], &state);
+    assert_eq!(participant_ids, [bot_ids[0]]);
+    assert_eq!(host.session().parties.len(), 1);
+}
+
+#[test_log::test]
+fn create_party_without_free_party_ids() {
+    let mut session = GameSession::<SampleStep>::new(TickId(0));
+    for _ in 0..0xff {
+        session.create_party().expect("should have a free party id");
+    }
+
+    assert!(matches!(
+        session.create_party(),
+        Err(HostLogicError::NoFreePartyIds)
+    ));
+    assert_eq!(session.parties.len(), 0xff);
+}
+
+/// Clamps moves to 100 and rejects moves to the left.
+struct TestStepValidator;
+
+impl StepValidator<SampleStep> for TestStepValidator {
+    fn validate(
+        &mut self,
+        _: ParticipantId,
+        _: TickId,
+        step: &SampleStep,
+    ) -> StepValidation<SampleStep> {
+        match step {
+            SampleStep::MoveLeft(_) => StepValidation::Reject,
+            SampleStep::MoveRight(amount) if *amount > 100 => {
+                StepValidation::Sanitize(SampleStep::MoveRight(100))
+            }
+            _ => StepValidation::Accept,
+        }
+    }
+}
+
+#[test_log::test]
+fn step_validator_sanitizes_rejects_and_kicks() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_step_validator(TestStepValidator);
+    host.set_step_violation_settings(StepViolationSettings {
+        max_violations: 2,
+        action: StepViolationAction::Kick,
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    send_steps(
+        &mut host,
+        connection_id,
+        participant_id,
+        TickId(0),
+        &[
+            SampleStep::Jump,
+            SampleStep::MoveRight(500),
+            SampleStep::MoveLeft(1),
+            SampleStep::Jump,
+        ],
+        &state,
+    );
+    assert_eq!(
+        host.session().participants[&participant_id]
+            .borrow()
+            .step_violations,
+        1
+    );
+
+    let authoritative_steps = send_steps(
+        &mut host,
+        connection_id,
+        participant_id,
+        TickId(2),
+        &[SampleStep::MoveLeft(1)],
+        &state,
+    );
+    assert_eq!(authoritative_steps.len(), 2);
+    assert_eq!(
+        authoritative_steps[1].get(&participant_id),
+        Some(&Step::Custom(SampleStep::MoveRight(100)))
+    );
+    assert!(host.session().participants.is_empty());
+}
+
+#[test_log::test]
+fn rate_limits_are_reported_as_events() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![0x42],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_rate_limits(RateLimits {
+        max_datagrams_per_second: 2,
+        max_commands_per_datagram: 2,
+        max_predicted_steps_ahead: 4,
+        max_download_requests_per_second: 1,
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    let now = Millis::from(0);
+    assert!(host.allow_datagram(connection_id, now, 1).unwrap());
+    assert!(host.allow_datagram(connection_id, now, 1).unwrap());
+    assert!(!host.allow_datagram(connection_id, now, 1).unwrap());
+    assert!(!host.allow_datagram(connection_id, now, 1).unwrap());
+
+    let next_second = Millis::from(1000);
+    assert!(host.allow_datagram(connection_id, next_second, 2).unwrap());
+    assert!(!host.allow_datagram(connection_id, next_second, 3).unwrap());
+
+    send_steps(
+        &mut host,
+        connection_id,
+        participant_id,
+        TickId(0),
+        &vec![SampleStep::Jump; 6],
+        &state,
+    );
+
+    for expected_answer_count in [2, 0] {
+        let answers = host
+            .update(
+                connection_id,
+                now,
+                &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest {
+                    request_id: 1,
+                }),
+                &state,
+            )
+            .expect("download request should be handled");
+        assert_eq!(answers.len(), expected_answer_count);
+    }
+
+    let violations: Vec<_> = host
+        .drain_events()
+        .filter_map(|event| match event {
+            HostEvent::RateLimitExceeded { violation, .. } => Some(violation),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        violations,
+        [
+            RateLimitViolation::DatagramsPerSecond,
+            RateLimitViolation::CommandsPerDatagram(3),
+            RateLimitViolation::PredictedStepsAhead(TickId(4)),
+            RateLimitViolation::DownloadRequestsPerSecond,
+        ]
+    );
+    assert_eq!(host.drain_events().count(), 0);
+}
+
+#[test_log::test]
+fn host_events_are_drained_in_order() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![0x42],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(16),
+        max_ticks_per_update: 4,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+    host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+        &state,
+    )
+    .expect("download request should be handled");
+
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(16 * 2)).expect("tick should work");
+
+    let unknown_participant_id = ParticipantId(99);
+    assert!(send_steps_result(
+        &mut host,
+        connection_id,
+        unknown_participant_id,
+        TickId(2),
+        &state
+    )
+    .is_err());
+
+    host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::LeaveGameType(LeaveGameRequest {
+            client_request_id: ClientRequestId(0),
+            local_indices: vec![0],
+        }),
+        &state,
+    )
+    .expect("leave should work");
+    host.destroy_connection(connection_id)
+        .expect("should destroy connection");
+
+    let events: Vec<_> = host.drain_events().collect();
+    debug!("{events:?}");
+    assert_eq!(events.len(), 8);
+    assert!(matches!(events[0], HostEvent::ConnectionCreated { .. }));
+    assert!(matches!(
+        events[1],
+        HostEvent::Connected {
+            session_id: DEFAULT_SESSION_ID,
+            ..
+        }
+    ));
+    assert!(matches!(
+        events[2],
+        HostEvent::ParticipantJoined { participant_id: id, .. } if id == participant_id
+    ));
+    assert!(matches!(
+        events[3],
+        HostEvent::DownloadStarted {
+            tick_id: TickId(0),
+            ..
+        }
+    ));
+    assert!(matches!(
+        events[4],
+        HostEvent::ForcedStep { participant_id: id, tick_id: TickId(1), .. } if id == participant_id
+    ));
+    assert!(matches!(
+        &events[5],
+        HostEvent::ProtocolError { message, .. } if message.contains("UnknownPartyMember")
+    ));
+    assert!(matches!(
+        events[6],
+        HostEvent::ParticipantLeft { participant_id: id, .. } if id == participant_id
+    ));
+    assert!(matches!(events[7], HostEvent::ConnectionDestroyed { .. }));
+}
+
+fn send_steps_result(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    participant_id: ParticipantId,
+    tick_id: TickId,
+    state: &TestStateProvider,
+) -> Result<Vec<HostToClientCommands<Step<SampleStep>>>, HostLogicError> {
+    let mut predicted_step = StepMap::new();
+    predicted_step
+        .insert(participant_id, SampleStep::Jump)
+        .expect("should be unique");
+    let steps_request = StepsRequest {
+        ack: StepsAck {
+            waiting_for_tick_id: TickId(0),
+        },
+        combined_predicted_steps: CombinedSteps {
+            tick_id,
+            steps: vec![predicted_step],
+        },
+    };
+    host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::Steps(steps_request),
+        state,
+    )
+}
+
+fn request_download(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    now: Millis,
+    state_provider: &CountingStateProvider,
+) -> TickId {
+    let answers = host
+        .update(
+            connection_id,
+            now,
+            &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+            state_provider,
+        )
+        .expect("download request should be handled");
+    let HostToClientCommands::DownloadGameState(response) = &answers[0] else {
+        panic!("expected download game state response");
+    };
+    response.tick_id
+}
+
+#[test_log::test]
+fn download_cache_shares_game_state_between_connections() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let counting_state = CountingStateProvider::default();
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(5), version);
+    host.set_download_cache_settings(DownloadCacheSettings {
+        expiry: MillisDuration::from_millis(100),
+        max_octet_size: 10,
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let other_connection_id = connect(&mut host, version, &state);
+
+    let now = Millis::from(0);
+    assert_eq!(
+        request_download(&mut host, connection_id, now, &counting_state),
+        TickId(5)
+    );
+    assert_eq!(
+        request_download(&mut host, other_connection_id, now, &counting_state),
+        TickId(5)
+    );
+    assert_eq!(counting_state.state_count.get(), 1);
+
+    let expired = Millis::from(100);
+    request_download(&mut host, connection_id, expired, &counting_state);
+    assert_eq!(counting_state.state_count.get(), 2);
+
+    host.set_download_cache_settings(DownloadCacheSettings {
+        expiry: MillisDuration::from_millis(100),
+        max_octet_size: 9,
+    });
+    request_download(&mut host, connection_id, expired, &counting_state);
+    request_download(&mut host, other_connection_id, expired, &counting_state);
+    assert_eq!(counting_state.state_count.get(), 4);
+}
+
+#[test_log::test]
+fn download_keeps_authoritative_steps_from_state_tick() {
+    const TICK_ID: TickId = TickId(10);
+    let state = TestStateProvider {
+        tick_id: TICK_ID,
+        payload: vec![],
+    };
+    let counting_state = CountingStateProvider::default();
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TICK_ID, version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(10),
+        max_ticks_per_update: 10,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let player_connection_id = connect(&mut host, version, &state);
+    join(&mut host, player_connection_id, &[0], &state);
+    let downloading_connection_id = connect(&mut host, version, &state);
+
+    let now = Millis::from(0);
+    assert_eq!(
+        request_download(&mut host, downloading_connection_id, now, &counting_state),
+        TICK_ID
+    );
+    host.tick(now).expect("should start the clock");
+    host.tick(Millis::from(30)).expect("tick should work");
+
+    // The player has received every step, but the steps from the game state are kept
+    assert!(ack(&mut host, player_connection_id, TICK_ID + 3, &state).is_empty());
+    assert_eq!(
+        host.get(downloading_connection_id)
+            .expect("should exist")
+            .waiting_for_tick_id(),
+        Some(TICK_ID)
+    );
+    let ranges = ack(&mut host, downloading_connection_id, TICK_ID, &state);
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0].tick_id, TICK_ID);
+    assert_eq!(ranges[0].steps.len(), 3);
+
+    // The cached game state can not be caught up from, once its steps are discarded
+    ack(&mut host, downloading_connection_id, TICK_ID + 3, &state);
+    let other_connection_id = connect(&mut host, version, &state);
+    assert_eq!(
+        request_download(&mut host, other_connection_id, now, &counting_state),
+        TICK_ID + 3
+    );
+    assert_eq!(counting_state.state_count.get(), 2);
+}
+
+#[test_log::test]
+fn blob_stream_settings_decide_how_the_state_is_sent() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![0x42; 10],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_blob_stream_settings(BlobStreamOutSettings {
+        chunk_size: 4,
+        resend_timing: ResendTiming::Fixed(Duration::from_millis(50)),
+        max_chunk_count_each_send: 2,
+        congestion_window: None,
+    });
+    let connection_id = connect(&mut host, version, &state);
+
+    let now = Millis::from(0);
+    let answers = host
+        .update(
+            connection_id,
+            now,
+            &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+            &state,
+        )
+        .expect("download request should be handled");
+    let HostToClientCommands::BlobStreamChannel(start_transfer) = &answers[1] else {
+        panic!("expected start transfer");
+    };
+    let SenderToReceiverFrontCommands::StartTransfer(start_transfer_data) = start_transfer else {
+        panic!("expected start transfer");
+    };
+    assert_eq!(start_transfer_data.chunk_size, 4);
+
+    let mut in_stream = FrontLogic::new();
+    in_stream
+        .receive(start_transfer)
+        .expect("should start transfer");
+    let ack_start = in_stream.send().expect("should work to send");
+
+    let set_chunk_sizes = |commands: &[HostToClientCommands<Step<SampleStep>>]| {
+        commands
+            .iter()
+            .filter_map(|command| match command {
+                HostToClientCommands::BlobStreamChannel(
+                    SenderToReceiverFrontCommands::SetChunk(set_chunk),
+                ) => Some(set_chunk.data.payload.len()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let answers = host
+        .update(
+            connection_id,
+            now,
+            &ClientToHostCommands::BlobStreamChannel(ack_start),
+            &state,
+        )
+        .expect("ack start should be handled");
+    assert_eq!(set_chunk_sizes(&answers), [4, 4]);
+
+    // The last chunk is sent, but the unacknowledged chunks are not resent before 50 ms
+    let commands = host
+        .send(connection_id, Millis::from(40))
+        .expect("send should work");
+    assert_eq!(set_chunk_sizes(&commands), [2]);
+
+    let commands = host
+        .send(connection_id, Millis::from(50))
+        .expect("send should work");
+    assert_eq!(set_chunk_sizes(&commands), [4, 4]);
+}
+
+#[test_log::test]
+fn game_step_response_reports_buffer_diagnostics() {
+    const TICK_ID: TickId = TickId(10);
+    let state = TestStateProvider {
+        tick_id: TICK_ID,
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TICK_ID, version);
+    let connection_id = connect(&mut host, version, &state);
+    let participant_id = join(&mut host, connection_id, &[0], &state)[0];
+
+    let predicted_steps = (0..3)
+        .map(|_| {
+            let mut predicted_step = StepMap::new();
+            predicted_step
+                .insert(participant_id, SampleStep::Jump)
+                .expect("should be unique");
+            predicted_step
+        })
+        .collect();
+
+    let answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::Steps(StepsRequest {
+                ack: StepsAck {
+                    waiting_for_tick_id: TICK_ID,
+                },
+                combined_predicted_steps: CombinedSteps {
+                    tick_id: TICK_ID,
+                    steps: predicted_steps,
+                },
+            }),
+            &state,
+        )
+        .expect("steps should be accepted");
+
+    let HostToClientCommands::GameStep(game_step) = &answers[0] else {
+        panic!("expected game step response");
+    };
+
+    assert_eq!(game_step.response_header.connection_buffer_count, 3);
+    assert_eq!(game_step.response_header.delta_buffer, 3);
+    assert_eq!(game_step.response_header.next_expected_tick_id, TICK_ID);
+}
+
+fn ack(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    waiting_for_tick_id: TickId,
+    state: &TestStateProvider,
+) -> Vec<CombinedSteps<Step<SampleStep>>> {
+    let answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::Steps(StepsRequest {
+                ack: StepsAck {
+                    waiting_for_tick_id,
+                },
+                combined_predicted_steps: CombinedSteps {
+                    tick_id: waiting_for_tick_id,
+                    steps: vec![],
+                },
+            }),
+            state,
+        )
+        .expect("steps should be accepted");
+    host.post_update().expect("post update should work");
+
+    let HostToClientCommands::GameStep(game_step) = &answers[0] else {
+        panic!("expected game step response");
+    };
+
+    game_step.authoritative_steps.ranges.clone()
+}
+
+#[test_log::test]
+fn authoritative_steps_are_sent_from_acknowledged_tick() {
+    const TICK_ID: TickId = TickId(10);
+    let state = TestStateProvider {
+        tick_id: TICK_ID,
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TICK_ID, version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(10),
+        max_ticks_per_update: 10,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let first_connection_id = connect(&mut host, version, &state);
+    let first_participant_id = join(&mut host, first_connection_id, &[0], &state)[0];
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(20)).expect("tick should work");
+
+    let second_connection_id = connect(&mut host, version, &state);
+    let second_participant_id = join(&mut host, second_connection_id, &[0], &state)[0];
+    host.tick(Millis::from(40)).expect("tick should work");
+
+    // A new range is started when the second participant joins
+    let ranges = ack(&mut host, first_connection_id, TICK_ID, &state);
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0].tick_id, TICK_ID);
+    assert_eq!(ranges[0].steps.len(), 2);
+    assert_eq!(ranges[1].tick_id, TICK_ID + 2);
+    assert_eq!(ranges[1].steps.len(), 2);
+    assert_eq!(
+        ranges[1].steps[0].get(&second_participant_id),
+        Some(&Step::Joined(JoinedData {
+            tick_id: TICK_ID + 2
+        }))
+    );
+    assert_eq!(
+        ranges[1].steps[0].get(&first_participant_id),
+        Some(&Step::Forced)
+    );
+
+    // Only the steps that have not been acknowledged are sent
+    let ranges = ack(&mut host, second_connection_id, TICK_ID + 1, &state);
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0].tick_id, TICK_ID + 1);
+    assert_eq!(ranges[0].steps.len(), 1);
+
+    let ranges = ack(&mut host, first_connection_id, TICK_ID + 3, &state);
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0].tick_id, TICK_ID + 3);
+    assert_eq!(ranges[0].steps.len(), 1);
+
+    // Everything before the lowest acknowledged tick is discarded
+    let ranges = ack(&mut host, first_connection_id, TICK_ID, &state);
+    assert_eq!(ranges[0].tick_id, TICK_ID + 1);
+}
+
+#[test_log::test]
+fn send_pushes_authoritative_steps_to_connection_without_participants() {
+    const TICK_ID: TickId = TickId(10);
+    let state = TestStateProvider {
+        tick_id: TICK_ID,
+        payload: vec![0x01, 0x02],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TICK_ID, version);
+    host.set_tick_settings(TickSettings {
+        tick_duration: MillisDuration::from_millis(10),
+        max_ticks_per_update: 10,
+        forced_step_grace_period: MillisDuration::from_millis(0),
+    });
+
+    let player_connection_id = connect(&mut host, version, &state);
+    join(&mut host, player_connection_id, &[0], &state);
+
+    let unconnected_connection_id = host.create_connection().expect("should create connection");
+    assert!(host
+        .send(unconnected_connection_id, Millis::from(0))
+        .expect("send should work")
+        .is_empty());
+
+    let idle_connection_id = connect(&mut host, version, &state);
+    host.tick(Millis::from(0)).expect("should start the clock");
+    host.tick(Millis::from(30)).expect("tick should work");
+
+    let commands = host
+        .send(idle_connection_id, Millis::from(30))
+        .expect("send should work");
+    assert_eq!(commands.len(), 1);
+    let HostToClientCommands::GameStep(game_step) = &commands[0] else {
+        panic!("expected game step");
+    };
+    assert_eq!(game_step.authoritative_steps.ranges.len(), 1);
+    assert_eq!(game_step.authoritative_steps.ranges[0].tick_id, TICK_ID);
+    assert_eq!(game_step.authoritative_steps.ranges[0].steps.len(), 3);
+
+    // Start transfer of a requested download is resent until it is acknowledged
+    host.update(
+        idle_connection_id,
+        Millis::from(30),
+        &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+        &state,
+    )
+    .expect("download should work");
+
+    let commands = host
+        .send(idle_connection_id, Millis::from(40))
+        .expect("send should work");
+    assert!(matches!(
+        commands[0],
+        HostToClientCommands::BlobStreamChannel(SenderToReceiverFrontCommands::StartTransfer(_))
+    ));
+}
+
+#[test_log::test]
+fn pings_are_answered_by_send_once_the_host_sends_on_schedule() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    let connection_id = connect(&mut host, version, &state);
+
+    // Without host initiated sends, the ping is answered right away
+    let commands = host
+        .update(
+            connection_id,
+            Millis::from(100),
+            &ClientToHostCommands::Ping(0x1234),
+            &state,
+        )
+        .expect("ping should work");
+    let [HostToClientCommands::Pong(pong)] = commands.as_slice() else {
+        panic!("expected pong");
+    };
+    assert_eq!(pong.lower_millis, 0x1234);
+
+    assert!(host
+        .send(connection_id, Millis::from(100))
+        .expect("send should work")
+        .is_empty());
+
+    let commands = host
+        .update(
+            connection_id,
+            Millis::from(110),
+            &ClientToHostCommands::Ping(0x2000),
+            &state,
+        )
+        .expect("ping should work");
+    assert!(commands.is_empty());
+
+    // The 15 ms the ping waited for the send is taken out of the pong
+    let commands = host
+        .send(connection_id, Millis::from(125))
+        .expect("send should work");
+    let [HostToClientCommands::Pong(pong)] = commands.as_slice() else {
+        panic!("expected pong");
+    };
+    assert_eq!(pong.lower_millis, 0x2000 + 15);
+
+    assert!(host
+        .send(connection_id, Millis::from(140))
+        .expect("send should work")
+        .is_empty());
+}
+
+fn assert_connection_rejected(
+    commands: &[HostToClientCommands<Step<SampleStep>>],
+    expected_reason: ConnectionRejectedReason,
+) {
+    let [HostToClientCommands::ConnectionRejected(rejected)] = commands else {
+        panic!("expected connection rejected");
+    };
+    assert_eq!(rejected.reason, expected_reason);
+}
+
+#[test_log::test]
+fn connect_is_rejected_with_reason() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+
+    let connection_id = host.create_connection().expect("it should work");
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        ConnectRequest {
+            nimble_version: nimble_protocol::Version::new(0, 0, 0),
+            ..connect_request(version)
+        },
+        &state,
+    );
+    assert_connection_rejected(&commands, ConnectionRejectedReason::NimbleVersionMismatch);
+
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        connect_request(Version::new(0, 1, 3)),
+        &state,
+    );
+    assert_connection_rejected(
+        &commands,
+        ConnectionRejectedReason::ApplicationVersionMismatch,
+    );
+    assert_eq!(
+        host.get(connection_id)
+            .expect("should find connection")
+            .phase(),
+        &Phase::WaitingForValidConnectRequest
+    );
+
+    host.set_max_connection_count(1);
+    connect(&mut host, version, &state);
+    let commands = request_connect(&mut host, connection_id, connect_request(version), &state);
+    assert_connection_rejected(&commands, ConnectionRejectedReason::SessionFull);
+
+    host.set_max_connection_count(2);
+    host.ban_connection(connection_id)
+        .expect("should find connection");
+    let commands = request_connect(&mut host, connection_id, connect_request(version), &state);
+    assert_connection_rejected(&commands, ConnectionRejectedReason::Banned);
+    assert_eq!(
+        host.get(connection_id)
+            .expect("should find connection")
+            .phase(),
+        &Phase::WaitingForValidConnectRequest
+    );
+}
+
+#[test_log::test]
+fn rejected_or_banned_connection_leaves_the_session() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![0x01, 0x02],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+
+    let connection_id = connect(&mut host, version, &state);
+    let join_accepted = request_join(
+        &mut host,
+        connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+    host.update(
+        connection_id,
+        Millis::from(0),
+        &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+        &state,
+    )
+    .expect("download should work");
+
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        connect_request(Version::new(0, 1, 3)),
+        &state,
+    );
+    assert_connection_rejected(
+        &commands,
+        ConnectionRejectedReason::ApplicationVersionMismatch,
+    );
+
+    let connection = host.get(connection_id).expect("should find connection");
+    assert_eq!(connection.party_id, None);
+    assert!(connection.participant_lookup.is_empty());
+    assert!(connection.out_blob_stream.is_none());
+    assert!(
+        host.session().parties[&join_accepted.party_and_session_secret.party_id]
+            .disconnected_at
+            .is_some()
+    );
+
+    let banned_connection_id = connect(&mut host, version, &state);
+    let banned_join_accepted = request_join(
+        &mut host,
+        banned_connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+    host.ban_connection(banned_connection_id)
+        .expect("should find connection");
+
+    let banned_connection = host
+        .get(banned_connection_id)
+        .expect("should find connection");
+    assert_eq!(
+        banned_connection.phase(),
+        &Phase::WaitingForValidConnectRequest
+    );
+    assert_eq!(banned_connection.party_id, None);
+    assert!(banned_connection.participant_lookup.is_empty());
+    assert!(
+        host.session().parties[&banned_join_accepted.party_and_session_secret.party_id]
+            .disconnected_at
+            .is_some()
+    );
+}
+
+fn assert_join_rejected(
+    host: &mut HostLogic<SampleStep>,
+    connection_id: HostConnectionId,
+    local_indices: &[u8],
+    state: &TestStateProvider,
+    expected_reason: JoinGameRejectedReason,
+) {
+    let result = request_join(
+        host,
+        connection_id,
+        JoinGameType::NoSecret,
+        local_indices,
+        state,
+    );
+    assert_eq!(result.err(), Some(expected_reason));
+}
+
+#[test_log::test]
+fn join_is_rejected_when_exceeding_participant_limits() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_participant_limits(ParticipantLimits {
+        max_participants: 3,
+        max_participants_per_connection: 2,
+        max_local_players_per_request: 2,
+    });
+
+    let connection_id = connect(&mut host, version, &state);
+    let other_connection_id = connect(&mut host, version, &state);
+
+    assert_join_rejected(
+        &mut host,
+        connection_id,
+        &[],
+        &state,
+        JoinGameRejectedReason::NoLocalPlayers,
+    );
+    assert_join_rejected(
+        &mut host,
+        connection_id,
+        &[0, 1, 2],
+        &state,
+        JoinGameRejectedReason::TooManyLocalPlayers,
+    );
+
+    assert_eq!(join(&mut host, connection_id, &[0], &state).len(), 1);
+    assert_eq!(join(&mut host, connection_id, &[1], &state).len(), 1);
+
+    assert_join_rejected(
+        &mut host,
+        connection_id,
+        &[2],
+        &state,
+        JoinGameRejectedReason::TooManyParticipantsForConnection,
+    );
+    assert_join_rejected(
+        &mut host,
+        other_connection_id,
+        &[0, 1],
+        &state,
+        JoinGameRejectedReason::SessionFull,
+    );
+
+    assert_eq!(join(&mut host, other_connection_id, &[0], &state).len(), 1);
+    assert_eq!(host.session().participants.len(), 3);
+}
+
+#[test_log::test]
+fn authenticator_decides_connect_and_join() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+    host.set_authenticator(TestTicketAuthenticator { max_local_index: 2 });
+
+    let connection_id = host.create_connection().expect("it should work");
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        ConnectRequest {
+            client_token: b"no ticket".to_vec(),
+            ..connect_request(version)
+        },
+        &state,
+    );
+    assert_connection_rejected(&commands, ConnectionRejectedReason::Unauthorized);
+
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        ConnectRequest {
+            client_token: b"ticket:ab".to_vec(),
+            ..connect_request(version)
+        },
+        &state,
+    );
+    assert!(matches!(
+        commands.as_slice(),
+        [HostToClientCommands::ConnectType(_)]
+    ));
+
+    assert_join_rejected(
+        &mut host,
+        connection_id,
+        &[1, 2],
+        &state,
+        JoinGameRejectedReason::Unauthorized,
+    );
+    assert!(host.session().participants.is_empty());
+
+    let participant_ids = join(&mut host, connection_id, &[1], &state);
+    assert_eq!(
+        host.session().participants[&participant_ids[0]]
+            .borrow()
+            .metadata,
+        ParticipantMetadata(vec![b'a', b'b', 1])
+    );
+}
+
+#[test_log::test]
+fn connections_choose_session() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+
+    let session_id = SessionId(1);
+    host.create_session(session_id, TickId(100))
+        .expect("should create session");
+    assert!(matches!(
+        host.create_session(session_id, TickId(0)),
+        Err(HostLogicError::SessionAlreadyExists(SessionId(1)))
+    ));
+
+    let default_connection_id = connect(&mut host, version, &state);
+    let connection_id = host.create_connection().expect("it should work");
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        ConnectRequest {
+            session_id: SessionId(9),
+            ..connect_request(version)
+        },
+        &state,
+    );
+    assert_connection_rejected(&commands, ConnectionRejectedReason::UnknownSession);
+    request_connect(
+        &mut host,
+        connection_id,
+        ConnectRequest {
+            session_id,
+            ..connect_request(version)
+        },
+        &state,
+    );
+
+    let default_participant_ids = join(&mut host, default_connection_id, &[0], &state);
+    let participant_ids = join(&mut host, connection_id, &[0, 1], &state);
+    assert_eq!(default_participant_ids, [ParticipantId(0)]);
+    assert_eq!(participant_ids, [ParticipantId(0), ParticipantId(1)]);
+    assert_eq!(host.session().participants.len(), 1);
+    assert_eq!(
+        host.get_session(session_id)
+            .expect("should find session")
+            .participants
+            .len(),
+        2
+    );
+
+    let answers = host
+        .update(
+            connection_id,
+            Millis::from(0),
+            &ClientToHostCommands::DownloadGameState(DownloadGameStateRequest { request_id: 1 }),
+            &TestSessionStateProvider,
+        )
+        .expect("should download game state");
+    let HostToClientCommands::DownloadGameState(download_response) = &answers[0] else {
+        panic!("expected download game state response");
+    };
+    assert_eq!(download_response.tick_id, TickId(100));
+
+    assert!(matches!(
+        host.remove_session(DEFAULT_SESSION_ID),
+        Err(HostLogicError::DefaultSessionCanNotBeRemoved)
+    ));
+    host.remove_session(session_id)
+        .expect("should remove session");
+    assert!(host.get_session(session_id).is_none());
+    assert_eq!(
+        host.get(connection_id)
+            .expect("should find connection")
+            .phase(),
+        &Phase::WaitingForValidConnectRequest
+    );
+    assert_eq!(
+        host.get(default_connection_id)
+            .expect("should find connection")
+            .phase(),
+        &Phase::Connected
+    );
+}
+
+#[test_log::test]
+fn connecting_to_another_session_leaves_the_current_one() {
+    let state = TestStateProvider {
+        tick_id: TickId(0),
+        payload: vec![],
+    };
+    let version = Version::new(0, 1, 2);
+    let mut host = HostLogic::<SampleStep>::new(TickId(0), version);
+
+    let session_id = SessionId(1);
+    host.create_session(session_id, TickId(0))
+        .expect("should create session");
+    let session_connect_request = ConnectRequest {
+        session_id,
+        ..connect_request(version)
+    };
+
+    let other_connection_id = host.create_connection().expect("it should work");
+    request_connect(
+        &mut host,
+        other_connection_id,
+        session_connect_request.clone(),
+        &state,
+    );
+    let other_join_accepted = request_join(
+        &mut host,
+        other_connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+
+    let connection_id = connect(&mut host, version, &state);
+    let default_join_accepted = request_join(
+        &mut host,
+        connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+    assert_eq!(
+        default_join_accepted.party_and_session_secret.party_id,
+        other_join_accepted.party_and_session_secret.party_id
+    );
+
+    let commands = request_connect(
+        &mut host,
+        connection_id,
+        session_connect_request.clone(),
+        &state,
+    );
+    assert_connection_rejected(&commands, ConnectionRejectedReason::DifferentSession);
+    assert!(
+        host.session().parties[&default_join_accepted.party_and_session_secret.party_id]
+            .disconnected_at
+            .is_some()
+    );
+
+    let commands = request_connect(&mut host, connection_id, session_connect_request, &state);
+    assert!(matches!(
+        commands.as_slice(),
+        [HostToClientCommands::ConnectType(_)]
+    ));
+    let join_accepted = request_join(
+        &mut host,
+        connection_id,
+        JoinGameType::NoSecret,
+        &[0],
+        &state,
+    )
+    .expect("join should work");
+
+    assert_ne!(
+        join_accepted.party_and_session_secret,
+        other_join_accepted.party_and_session_secret
+    );
+    let session = host.get_session(session_id).expect("should find session");
+    assert_eq!(
+        session.parties[&other_join_accepted.party_and_session_secret.party_id].participant_ids,
+        [other_join_accepted.participants.0[0].participant_id]
+    );
+    assert_eq!(
+        session.parties[&join_accepted.party_and_session_secret.party_id].participant_ids,
+        [join_accepted.participants.0[0].participant_id]
+    );
+}
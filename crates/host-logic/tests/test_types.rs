@@ -3,7 +3,12 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 
-use nimble_host_logic::GameStateProvider;
+use nimble_host_logic::authenticator::{Authenticator, ParticipantMetadata};
+use nimble_host_logic::{GameStateProvider, SessionGameStateProvider};
+use nimble_protocol::client_to_host::{ConnectRequest, JoinGameRequest};
+use nimble_protocol::host_to_client::{ConnectionRejectedReason, JoinGameRejectedReason};
+use nimble_protocol::SessionId;
+use std::cell::Cell;
 use tick_id::TickId;
 
 pub struct TestStateProvider {
@@ -16,3 +21,72 @@ impl GameStateProvider for TestStateProvider {
         (self.tick_id, self.payload.clone())
     }
 }
+
+/// Provides a state that tells which session it was requested for.
+pub struct TestSessionStateProvider;
+
+impl SessionGameStateProvider for TestSessionStateProvider {
+    fn session_state(&self, session_id: SessionId, tick_id: TickId) -> (TickId, Vec<u8>) {
+        (tick_id, session_id.0.to_be_bytes().to_vec())
+    }
+}
+
+/// Counts how many times the game state has been serialized.
+#[derive(Default)]
+pub struct CountingStateProvider {
+    pub state_count: Cell<usize>,
+}
+
+impl SessionGameStateProvider for CountingStateProvider {
+    fn session_state(&self, _: SessionId, tick_id: TickId) -> (TickId, Vec<u8>) {
+        self.state_count.set(self.state_count.get() + 1);
+        (tick_id, vec![0x42; 10])
+    }
+}
+
+/// Stand-in for a backend ticket check. Accepts client tokens that start with `ticket:`,
+/// and only lets local indices below `max_local_index` join.
+pub struct TestTicketAuthenticator {
+    pub max_local_index: u8,
+}
+
+impl TestTicketAuthenticator {
+    const TICKET_PREFIX: &'static [u8] = b"ticket:";
+}
+
+impl Authenticator for TestTicketAuthenticator {
+    fn authenticate_connect(
+        &mut self,
+        connect_request: &ConnectRequest,
+    ) -> Result<(), ConnectionRejectedReason> {
+        if connect_request
+            .client_token
+            .starts_with(Self::TICKET_PREFIX)
+        {
+            Ok(())
+        } else {
+            Err(ConnectionRejectedReason::Unauthorized)
+        }
+    }
+
+    fn authenticate_join(
+        &mut self,
+        client_token: &[u8],
+        join_request: &JoinGameRequest,
+    ) -> Result<Vec<ParticipantMetadata>, JoinGameRejectedReason> {
+        let user = &client_token[Self::TICKET_PREFIX.len()..];
+        join_request
+            .player_requests
+            .players
+            .iter()
+            .map(|player| {
+                if player.local_index >= self.max_local_index {
+                    return Err(JoinGameRejectedReason::Unauthorized);
+                }
+                let mut metadata = user.to_vec();
+                metadata.push(player.local_index);
+                Ok(ParticipantMetadata(metadata))
+            })
+            .collect()
+    }
+}
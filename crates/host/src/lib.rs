@@ -26,12 +26,24 @@ use flood_rs::prelude::OutOctetStream;
 use flood_rs::{Deserialize, Serialize};
 use hexify::format_hex;
 use log::{debug, trace};
-use monotonic_time_rs::Millis;
+use monotonic_time_rs::{Millis, MillisDuration};
+use nimble_blob_stream::prelude::BlobStreamOutSettings;
 use nimble_host_logic::{
-    connection::Connection, session::GameSession, GameStateProvider, HostLogic,
+    authenticator::Authenticator,
+    bot::Bot,
+    connection::Connection,
+    download_cache::DownloadCacheSettings,
+    event::HostEvent,
+    missing_step::MissingStepPolicy,
+    rate_limit::RateLimits,
+    session::{GameSession, ParticipantLimits},
+    tick_clock::TickSettings,
+    validator::{StepValidator, StepViolationSettings},
+    HostLogic, SessionGameStateProvider,
 };
 use nimble_layer::NimbleLayer;
-use nimble_protocol::prelude::ClientToHostCommands;
+use nimble_participant::ParticipantId;
+use nimble_protocol::prelude::{ClientToHostCommands, SessionId};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use tick_id::TickId;
@@ -104,12 +116,335 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
         self.logic.get(connection_id)
     }
 
-    /// Returns a reference to the current game session.
+    /// Sets the tick duration, catch-up limit and forced step grace period used by [`Host::tick`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tick_settings` - The settings for producing authoritative steps on a clock.
+    pub fn set_tick_settings(&mut self, tick_settings: TickSettings) {
+        self.logic.set_tick_settings(tick_settings);
+    }
+
+    /// Sets for how long the participants of a destroyed connection wait for the client
+    /// to join again with its party secret, before they are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `reconnect_timeout` - The time to wait before the participants are removed.
+    pub fn set_reconnect_timeout(&mut self, reconnect_timeout: MillisDuration) {
+        self.logic.set_reconnect_timeout(reconnect_timeout);
+    }
+
+    /// Sets the maximum number of connected connections. Connect requests beyond that are
+    /// rejected with `ConnectionRejectedReason::SessionFull`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connection_count` - The maximum number of connected connections.
+    pub fn set_max_connection_count(&mut self, max_connection_count: usize) {
+        self.logic.set_max_connection_count(max_connection_count);
+    }
+
+    /// Sets the authenticator that decides which connect and join game requests that are accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `authenticator` - The authenticator to consult, e.g. one that checks tickets from a backend.
+    pub fn set_authenticator(&mut self, authenticator: impl Authenticator + 'static) {
+        self.logic.set_authenticator(authenticator);
+    }
+
+    /// Sets the validator that accepts, sanitizes or rejects the predicted steps from clients,
+    /// e.g. to stop out-of-range values from reaching the other peers.
+    ///
+    /// # Arguments
+    ///
+    /// * `step_validator` - The validator to consult for every incoming predicted step.
+    pub fn set_step_validator(&mut self, step_validator: impl StepValidator<StepT> + 'static) {
+        self.logic.set_step_validator(step_validator);
+    }
+
+    /// Sets how many rejected steps a participant can have, and what happens to it after that.
+    ///
+    /// # Arguments
+    ///
+    /// * `step_violation_settings` - The maximum number of violations and the action to take.
+    pub fn set_step_violation_settings(&mut self, step_violation_settings: StepViolationSettings) {
+        self.logic
+            .set_step_violation_settings(step_violation_settings);
+    }
+
+    /// Sets the budgets for what each connection can send, e.g. datagrams per second and
+    /// download requests. Violations are reported as `HostEvent::RateLimitExceeded`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limits` - The budgets for datagrams, commands, predicted steps and downloads.
+    pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+        self.logic.set_rate_limits(rate_limits);
+    }
+
+    /// Sets how long game states are shared between downloading connections, and how much
+    /// memory they can use, for every session.
+    ///
+    /// # Arguments
+    ///
+    /// * `download_cache_settings` - The expiry and the maximum octet size of the cache.
+    pub fn set_download_cache_settings(&mut self, download_cache_settings: DownloadCacheSettings) {
+        self.logic
+            .set_download_cache_settings(download_cache_settings);
+    }
+
+    /// Sets how the game state is sent to downloading connections. Downloads that have already
+    /// started keep their settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `blob_stream_settings` - The chunk size, the resend timing and the number of chunks
+    ///   to send at a time.
+    pub fn set_blob_stream_settings(&mut self, blob_stream_settings: BlobStreamOutSettings) {
+        self.logic.set_blob_stream_settings(blob_stream_settings);
+    }
+
+    /// Removes and returns the events that have happened in the host logic since the last call.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the events, oldest first.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = HostEvent> + '_ {
+        self.logic.drain_events()
+    }
+
+    /// Sets the limits for how many participants that can join. Join game requests beyond
+    /// them are rejected with a `JoinGameRejected`.
+    ///
+    /// # Arguments
+    ///
+    /// * `participant_limits` - The participant limits for the session, connections and requests.
+    pub fn set_participant_limits(&mut self, participant_limits: ParticipantLimits) {
+        self.logic.set_participant_limits(participant_limits);
+    }
+
+    /// Sets the policy that decides the steps for participants that have not provided a step in
+    /// time, e.g. repeating their last step or waiting for everyone in lockstep.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to set the policy for.
+    /// * `policy` - The missing step policy for the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session could not be found.
+    pub fn set_missing_step_policy(
+        &mut self,
+        session_id: SessionId,
+        policy: impl MissingStepPolicy<StepT> + 'static,
+    ) -> Result<(), HostError> {
+        self.logic.set_missing_step_policy(session_id, policy)?;
+        Ok(())
+    }
+
+    /// Bans the connection, so every connect request from it is rejected with
+    /// `ConnectionRejectedReason::Banned`.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_id` - The ID of the connection to ban.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the connection could not be found.
+    pub fn ban_connection(
+        &mut self,
+        connection_id: nimble_host_logic::HostConnectionId,
+    ) -> Result<(), HostError> {
+        self.logic.ban_connection(connection_id)?;
+        Ok(())
+    }
+
+    /// Produces authoritative steps on a fixed clock, for every session.
+    ///
+    /// Should be called regularly (e.g. every frame) so the authoritative timelines keep advancing
+    /// even if no client sends anything. Participants that have not provided a step before the
+    /// deadline and grace period of a tick get the step from the missing step policy of the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time in milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the authoritative steps could not be produced.
+    pub fn tick(&mut self, now: Millis) -> Result<(), HostError> {
+        self.logic.tick(now)?;
+        Ok(())
+    }
+
+    /// Returns a reference to the default game session.
     #[must_use]
-    pub const fn session(&self) -> &GameSession<StepT> {
+    pub fn session(&self) -> &GameSession<StepT> {
         self.logic.session()
     }
 
+    /// Returns a reference to a game session by its identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session.
+    #[must_use]
+    pub fn get_session(&self, session_id: SessionId) -> Option<&GameSession<StepT>> {
+        self.logic.get_session(session_id)
+    }
+
+    /// Creates a new game session that clients can choose in their connect request.
+    /// Each session has its own participants and authoritative timeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier that clients use to connect to the session.
+    /// * `tick_id` - The initial tick identifier for the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if there is already a session with the identifier.
+    pub fn create_session(
+        &mut self,
+        session_id: SessionId,
+        tick_id: TickId,
+    ) -> Result<(), HostError> {
+        self.logic.create_session(session_id, tick_id)?;
+        Ok(())
+    }
+
+    /// Adds a custom step from the host to the authoritative step of a tick, under the reserved
+    /// `HOST_PARTICIPANT_ID`, e.g. for spawn events, round timers or admin actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to add the step to.
+    /// * `tick_id` - The tick that the step should be produced for. Must not be produced yet.
+    /// * `step` - The custom step.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found, the tick has already been produced,
+    /// or the tick already has a host step.
+    pub fn push_host_step(
+        &mut self,
+        session_id: SessionId,
+        tick_id: TickId,
+        step: StepT,
+    ) -> Result<(), HostError> {
+        self.logic.push_host_step(session_id, tick_id, step)?;
+        Ok(())
+    }
+
+    /// Adds a bot participant to a game session. The bot has no connection and provides the
+    /// step of the participant for every produced tick. When the session is full, joining
+    /// players take over the participants of bots.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to add the bot to.
+    /// * `bot` - Provides the steps of the participant.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found or is full.
+    ///
+    /// # Returns
+    ///
+    /// The `ParticipantId` of the bot.
+    pub fn add_bot(
+        &mut self,
+        session_id: SessionId,
+        bot: impl Bot<StepT> + 'static,
+    ) -> Result<ParticipantId, HostError> {
+        Ok(self.logic.add_bot(session_id, bot)?)
+    }
+
+    /// Removes a bot participant from a game session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session.
+    /// * `participant_id` - The participant of the bot.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found or the participant is not a bot.
+    pub fn remove_bot(
+        &mut self,
+        session_id: SessionId,
+        participant_id: ParticipantId,
+    ) -> Result<(), HostError> {
+        self.logic.remove_bot(session_id, participant_id)?;
+        Ok(())
+    }
+
+    /// Pauses a game session. No authoritative steps are produced until it is resumed,
+    /// and the clients stop predicting past the tick it was paused at.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to pause.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found.
+    pub fn pause_session(&mut self, session_id: SessionId) -> Result<(), HostError> {
+        self.logic.pause_session(session_id)?;
+        Ok(())
+    }
+
+    /// Resumes a paused game session from the tick it was paused at, after the resume delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to resume.
+    /// * `now` - The current time in milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found.
+    pub fn resume_session(&mut self, session_id: SessionId, now: Millis) -> Result<(), HostError> {
+        self.logic.resume_session(session_id, now)?;
+        Ok(())
+    }
+
+    /// Sets if clients with joined participants can pause and resume their session.
+    ///
+    /// # Arguments
+    ///
+    /// * `clients_can_pause` - `true` if session pause requests from clients should be accepted.
+    pub fn set_clients_can_pause(&mut self, clients_can_pause: bool) {
+        self.logic.set_clients_can_pause(clients_can_pause);
+    }
+
+    /// Sets how long a paused session waits before it continues after a resume request.
+    ///
+    /// # Arguments
+    ///
+    /// * `resume_delay` - The time to wait, zero resumes at once.
+    pub fn set_resume_delay(&mut self, resume_delay: MillisDuration) {
+        self.logic.set_resume_delay(resume_delay);
+    }
+
+    /// Removes a game session. The connections in the session must connect again.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The identifier of the session to remove.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HostError` if the session is not found or is the default session.
+    pub fn remove_session(&mut self, session_id: SessionId) -> Result<(), HostError> {
+        self.logic.remove_session(session_id)?;
+        Ok(())
+    }
+
     /// Updates the host state based on incoming datagrams from a client.
     ///
     /// Processes the datagram, updates game logic, and prepares outgoing datagrams to be sent back to the client.
@@ -119,7 +454,7 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
     /// * `connection_id` - The ID of the connection sending the datagram.
     /// * `now` - The current time in milliseconds.
     /// * `datagram` - The incoming datagram data.
-    /// * `state_provider` - A reference to an implementation providing game state if needed.
+    /// * `state_provider` - A reference to an implementation providing the game state of the sessions if needed.
     ///
     /// # Returns
     ///
@@ -133,7 +468,7 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
         connection_id: nimble_host_logic::HostConnectionId,
         now: Millis,
         datagram: &[u8],
-        state_provider: &impl GameStateProvider,
+        state_provider: &impl SessionGameStateProvider,
     ) -> Result<Vec<Vec<u8>>, HostError> {
         trace!(
             "time:{now}: host received for connection:{} payload:\n{}",
@@ -152,6 +487,13 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
             ClientToHostCommands<StepT>,
         >(datagram_without_layer)?;
 
+        if !self
+            .logic
+            .allow_datagram(connection_id, now, deserialized_commands.len())?
+        {
+            return Ok(vec![]);
+        }
+
         let mut all_commands_to_send = Vec::new();
         for deserialized_command in deserialized_commands {
             let commands_to_send =
@@ -161,10 +503,53 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
             all_commands_to_send.extend(commands_to_send);
         }
 
-        self.logic.post_update();
+        self.logic.post_update()?;
+
+        Self::to_datagrams(found_connection, &all_commands_to_send)
+    }
+
+    /// Creates datagrams that the host sends on its own schedule to a client.
+    ///
+    /// Should be called regularly for each connection, so connections without participants
+    /// (e.g. spectators) receive authoritative steps, and lost blob stream chunks are resent.
+    /// Once called for a connection, its pings are answered with pongs here as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_id` - The ID of the connection to send to.
+    /// * `now` - The current time in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of outgoing datagrams or a `HostError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// `HostError` TODO:
+    pub fn send(
+        &mut self,
+        connection_id: nimble_host_logic::HostConnectionId,
+        now: Millis,
+    ) -> Result<Vec<Vec<u8>>, HostError> {
+        let found_connection = self
+            .connections
+            .get_mut(&connection_id.0)
+            .ok_or(HostError::ConnectionNotFound(connection_id.0))?;
+
+        let commands_to_send = self.logic.send(connection_id, now)?;
+        if commands_to_send.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Self::to_datagrams(found_connection, &commands_to_send)
+    }
 
+    fn to_datagrams(
+        connection: &mut HostConnection,
+        commands: &[impl Serialize],
+    ) -> Result<Vec<Vec<u8>>, HostError> {
         let mut datagram_chunker = DatagramChunker::new(1024);
-        for cmd in all_commands_to_send {
+        for cmd in commands {
             let mut out_stream = OutOctetStream::new();
             cmd.serialize(&mut out_stream)?;
             datagram_chunker.push(out_stream.octets_ref())?;
@@ -172,7 +557,7 @@ impl<StepT: Clone + Deserialize + Serialize + Eq + Debug + Display> Host<StepT>
 
         let outgoing_datagrams = datagram_chunker.finalize();
 
-        let out_datagrams = found_connection.layer.send(&outgoing_datagrams)?;
+        let out_datagrams = connection.layer.send(&outgoing_datagrams)?;
 
         for (index, datagram) in out_datagrams.iter().enumerate() {
             trace!(
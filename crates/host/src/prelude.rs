@@ -7,7 +7,29 @@ pub use {
     crate::{err::HostError, Host},
     datagram_chunker::DatagramChunkerError,
     err_rs::{ErrorLevel, ErrorLevelProvider},
+    nimble_blob_stream::prelude::{BlobStreamOutSettings, ResendTiming},
+    nimble_host_logic::authenticator::{
+        AcceptAllAuthenticator, Authenticator, ParticipantMetadata,
+    },
+    nimble_host_logic::bot::Bot,
+    nimble_host_logic::download_cache::DownloadCacheSettings,
     nimble_host_logic::err::HostLogicError,
-    nimble_host_logic::{GameStateProvider, HostConnectionId},
+    nimble_host_logic::event::HostEvent,
+    nimble_host_logic::missing_step::{
+        DefaultStepPolicy, ForcedStepPolicy, LockstepPolicy, MissingStepPolicy,
+        RepeatLastCustomStepPolicy,
+    },
+    nimble_host_logic::rate_limit::{RateLimitViolation, RateLimits},
+    nimble_host_logic::session::ParticipantLimits,
+    nimble_host_logic::validator::{
+        AcceptAllStepValidator, StepValidation, StepValidator, StepViolationAction,
+        StepViolationSettings,
+    },
+    nimble_host_logic::{
+        tick_clock::TickSettings, GameStateProvider, HostConnectionId, SessionGameStateProvider,
+        DEFAULT_SESSION_ID,
+    },
     nimble_layer::NimbleLayerError,
+    nimble_participant::HOST_PARTICIPANT_ID,
+    nimble_protocol::SessionId,
 };
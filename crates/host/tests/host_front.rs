@@ -37,10 +37,12 @@ fn create_and_connect<
 
         // Commands
         0x05,               // Connect Request: ClientToHostOobCommand::ConnectType = 0x05
-        0, 0, 0, 0, 0, 5,   // Nimble version
+        0, 0, 0, 0, 0, 9,   // Nimble version
         0,                  // Flags (use debug stream). Not used yet.
         0, 0, 0, 1, 0, 2,   // Application version
         0,                  // Client Request Id
+        0, 0,               // Session Id
+        0, 0,               // Client token octet count
     ];
 
     let application_version = app_version::Version::new(0, 1, 2);
@@ -104,6 +106,9 @@ fn join_game() -> Result<(), HostError> {
     assert_eq!(participant.borrow().id.0, 0);
     assert_eq!(participant.borrow().client_local_index, 0x42);
 
+    let party = host.session().parties.get(&0).expect("should have party");
+    let secret = party.secret.value.to_be_bytes();
+
     #[rustfmt::skip]
     let expected_join_response: &[u8] = &[
         // Header
@@ -112,7 +117,7 @@ fn join_game() -> Result<(), HostError> {
         // Commands
         0x09, // Join Game Response
         0x00, // Client Request ID
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // SECRET
+        secret[0], secret[1], secret[2], secret[3], secret[4], secret[5], secret[6], secret[7], // SECRET, random for each party
         0x00, // Party ID - Only for debug purposes. Maybe should be removed?
         0x02, // Number of participants that joined
         0x42, // The index of the first local player
@@ -158,6 +163,8 @@ fn game_step() -> Result<(), HostError> {
         0x00, // Buffer count
         0x00, // Signed 8-bit delta tick
         0x00, 0x00, 0x00, 0x00, // Next Expected TickID. Signals that it has not received anything yet.
+        0x00, // Session is not paused
+        0x00, // No resume is scheduled
 
         // Authoritative Steps
         0x00, 0x00, 0x00, 0x00, // Start TickID
@@ -68,10 +68,12 @@ pub fn client_connect_with_layer() -> Result<(), TestError> {
         0x00, 0x00, // Datagram ID
         // Commands
         0x05, // Connect
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x05, // Nimble Version
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x09, // Nimble Version
         0x00, // Flags
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Application Version
         0x00, // Request ID
+        0x00, 0x00, // Session ID
+        0x00, 0x00, // Client Token Octet Count
     ];
 
     assert_eq_slices(datagram, expected);
@@ -87,10 +89,12 @@ pub fn client_connect_with_layer() -> Result<(), TestError> {
         0x00, 0x01, // Datagram ID
         // Commands
         0x05, // Connect
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x05, // Nimble Version
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x09, // Nimble Version
         0x00, // Flags
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Application Version
         0x00, // Request ID
+        0x00, 0x00, // Session ID
+        0x00, 0x00, // Client Token Octet Count
     ];
 
     assert_eq_slices(&datagrams_after[0], expected_after);
@@ -106,7 +110,7 @@ pub fn client_connect_with_layer() -> Result<(), TestError> {
         ];
 
         now = Millis::from(0xf000 + 200u64 + index as u64 * 20);
-        layer.receive(&feed)?;
+        layer.receive(feed)?;
         if index % 2 == 0 {
             let _ = send(now, &mut logic, &mut layer)?;
         }
@@ -20,15 +20,13 @@ use nimble_sample_step::SampleStep;
 
 fn communicate<
     GameT: BufferDeserializer + VersionProvider + GameCallbacks<StepT> + Debug,
-    StepT: Clone + Deserialize + Debug + Display + Eq + PartialEq,
+    StepT: Clone + Deserialize + Serialize + Debug + Display + Eq + PartialEq,
 >(
     host: &mut Host<Step<StepT>>,
     state_provider: &impl GameStateProvider,
     connection_id: HostConnectionId,
     client: &mut Client<GameT, StepT>,
-) where
-    StepT: Serialize,
-{
+) {
     let now = Millis::new(0);
 
     let to_host = client.send(now).expect("should work");
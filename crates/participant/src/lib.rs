@@ -42,6 +42,10 @@ use std::fmt::Display;
 #[derive(PartialEq, Eq, Copy, Ord, Hash, Clone, Debug, PartialOrd)]
 pub struct ParticipantId(pub u8);
 
+/// Reserved for the steps that the host itself adds to the authoritative steps,
+/// e.g. spawn events or round timers. It is never given to a joining participant.
+pub const HOST_PARTICIPANT_ID: ParticipantId = ParticipantId(0xff);
+
 impl Serialize for ParticipantId {
     /// Serializes the `ParticipantId` into the given stream.
     ///
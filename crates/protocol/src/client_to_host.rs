@@ -4,7 +4,7 @@
  */
 use crate::host_to_client::TickIdUtil;
 use crate::serialize::CombinedSteps;
-use crate::{ClientRequestId, SessionConnectionSecret, Version};
+use crate::{ClientRequestId, SessionConnectionSecret, SessionId, Version};
 use flood_rs::{Deserialize, ReadOctetStream, Serialize, WriteOctetStream};
 use nimble_blob_stream::prelude::ReceiverToSenderFrontCommands;
 use nimble_participant::ParticipantId;
@@ -20,6 +20,8 @@ enum ClientToHostCommand {
     BlobStreamChannel = 0x04,
     Connect = 0x05,
     Ping = 0x06,
+    LeaveGame = 0x07,
+    SessionPause = 0x11,
 }
 
 impl TryFrom<u8> for ClientToHostCommand {
@@ -33,6 +35,8 @@ impl TryFrom<u8> for ClientToHostCommand {
             0x04 => Self::BlobStreamChannel,
             0x05 => Self::Connect,
             0x06 => Self::Ping,
+            0x07 => Self::LeaveGame,
+            0x11 => Self::SessionPause,
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Unknown ClientToHostCommand {value}"),
@@ -41,22 +45,39 @@ impl TryFrom<u8> for ClientToHostCommand {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The maximum number of octets in the client token of a connect request.
+pub const MAX_CLIENT_TOKEN_OCTET_COUNT: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConnectRequest {
     pub nimble_version: Version,
     pub use_debug_stream: bool,
     pub application_version: Version,
     pub client_request_id: ClientRequestId,
+    /// The game session on the host that the client wants to connect to.
+    pub session_id: SessionId,
+    /// Opaque token, e.g. a ticket issued by a backend, that the host can use to authenticate the client.
+    pub client_token: Vec<u8>,
 }
 impl ConnectRequest {
     /// # Errors
     ///
     /// `io::Error` // TODO:
+    #[allow(clippy::cast_possible_truncation)]
     pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        if self.client_token.len() > MAX_CLIENT_TOKEN_OCTET_COUNT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("client token is too long {}", self.client_token.len()),
+            ));
+        }
         self.nimble_version.to_stream(stream)?;
         stream.write_u8(u8::from(self.use_debug_stream))?;
         self.application_version.to_stream(stream)?;
         self.client_request_id.serialize(stream)?;
+        self.session_id.serialize(stream)?;
+        stream.write_u16(self.client_token.len() as u16)?;
+        stream.write(&self.client_token)?;
         Ok(())
     }
 
@@ -69,6 +90,19 @@ impl ConnectRequest {
             use_debug_stream: stream.read_u8()? != 0,
             application_version: Version::from_stream(stream)?,
             client_request_id: ClientRequestId::deserialize(stream)?,
+            session_id: SessionId::deserialize(stream)?,
+            client_token: {
+                let octet_count = stream.read_u16()? as usize;
+                if octet_count > MAX_CLIENT_TOKEN_OCTET_COUNT {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("client token is too long {octet_count}"),
+                    ));
+                }
+                let mut client_token = vec![0u8; octet_count];
+                stream.read(&mut client_token)?;
+                client_token
+            },
         })
     }
 }
@@ -105,6 +139,8 @@ pub enum ClientToHostCommands<StepT: Clone + Debug + Serialize + Deserialize + D
     BlobStreamChannel(ReceiverToSenderFrontCommands),
     ConnectType(ConnectRequest),
     Ping(u16),
+    LeaveGameType(LeaveGameRequest),
+    SessionPause(SessionPauseRequest),
 }
 
 impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Serialize
@@ -119,6 +155,8 @@ impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Serialize
             Self::BlobStreamChannel(blob_stream_command) => blob_stream_command.to_stream(stream),
             Self::ConnectType(connect_request) => connect_request.to_stream(stream),
             Self::Ping(ping_time) => stream.write_u16(*ping_time),
+            Self::LeaveGameType(leave_game_request) => leave_game_request.to_stream(stream),
+            Self::SessionPause(session_pause_request) => session_pause_request.to_stream(stream),
         }
     }
 }
@@ -142,6 +180,12 @@ impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Deserialize
             }
             ClientToHostCommand::Connect => Self::ConnectType(ConnectRequest::from_stream(stream)?),
             ClientToHostCommand::Ping => Self::Ping(stream.read_u16()?),
+            ClientToHostCommand::LeaveGame => {
+                Self::LeaveGameType(LeaveGameRequest::from_stream(stream)?)
+            }
+            ClientToHostCommand::SessionPause => {
+                Self::SessionPause(SessionPauseRequest::from_stream(stream)?)
+            }
         };
         Ok(x)
     }
@@ -162,6 +206,8 @@ impl<StepT: Deserialize + Serialize + Debug + Display + Clone> From<&ClientToHos
             }
             ClientToHostCommands::ConnectType(_) => ClientToHostCommand::Connect as Self,
             ClientToHostCommands::Ping(_) => ClientToHostCommand::Ping as Self,
+            ClientToHostCommands::LeaveGameType(_) => ClientToHostCommand::LeaveGame as Self,
+            ClientToHostCommands::SessionPause(_) => ClientToHostCommand::SessionPause as Self,
         }
     }
 }
@@ -181,8 +227,10 @@ impl<StepT: Clone + Debug + Eq + PartialEq + Serialize + Deserialize + Display>
             Self::BlobStreamChannel(blob_command) => {
                 write!(f, "blob stream channel {blob_command:?}")
             }
-            &Self::ConnectType(connect_request) => write!(f, "connect {connect_request:?}"),
+            Self::ConnectType(connect_request) => write!(f, "connect {connect_request:?}"),
             Self::Ping(_) => write!(f, "ping"),
+            Self::LeaveGameType(leave) => write!(f, "leave {leave:?}"),
+            Self::SessionPause(session_pause) => write!(f, "session pause {session_pause:?}"),
         }
     }
 }
@@ -379,6 +427,73 @@ impl JoinGameRequest {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaveGameRequest {
+    pub client_request_id: ClientRequestId,
+    pub local_indices: Vec<u8>,
+}
+
+impl LeaveGameRequest {
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        self.client_request_id.serialize(stream)?;
+        stream.write_u8(u8::try_from(self.local_indices.len()).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("too many players {err}"),
+            )
+        })?)?;
+        for local_index in &self.local_indices {
+            stream.write_u8(*local_index)?;
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn from_stream(stream: &mut impl ReadOctetStream) -> io::Result<Self> {
+        let client_request_id = ClientRequestId::deserialize(stream)?;
+        let count = stream.read_u8()?;
+        let mut local_indices = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            local_indices.push(stream.read_u8()?);
+        }
+
+        Ok(Self {
+            client_request_id,
+            local_indices,
+        })
+    }
+}
+
+/// Asks the host to pause or resume the game session. The host only accepts it if it allows
+/// clients to pause, and the connection has joined participants.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SessionPauseRequest {
+    pub paused: bool,
+}
+
+impl SessionPauseRequest {
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        stream.write_u8(u8::from(self.paused))
+    }
+
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn from_stream(stream: &mut impl ReadOctetStream) -> io::Result<Self> {
+        Ok(Self {
+            paused: stream.read_u8()? != 0,
+        })
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct StepsAck {
     pub waiting_for_tick_id: TickId,
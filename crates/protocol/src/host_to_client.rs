@@ -20,10 +20,13 @@ use tick_id::TickId;
 pub enum HostToClientCommand {
     GameStep = 0x08,
     JoinGame = 0x09,
+    LeaveGame = 0x0A,
     DownloadGameState = 0x0B,
     BlobStreamChannel = 0x0C,
     Connect = 0x0D,
     Pong = 0x0E,
+    ConnectionRejected = 0x0F,
+    JoinGameRejected = 0x10,
 }
 
 impl TryFrom<u8> for HostToClientCommand {
@@ -33,10 +36,13 @@ impl TryFrom<u8> for HostToClientCommand {
         Ok(match value {
             0x09 => Self::JoinGame,
             0x08 => Self::GameStep,
+            0x0A => Self::LeaveGame,
             0x0B => Self::DownloadGameState,
             0x0C => Self::BlobStreamChannel,
             0x0D => Self::Connect,
             0x0E => Self::Pong,
+            0x0F => Self::ConnectionRejected,
+            0x10 => Self::JoinGameRejected,
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Unknown host to client command 0x{value:0X}"),
@@ -172,6 +178,91 @@ impl ConnectionAccepted {
     }
 }
 
+/// The reason the host gives for not accepting a connect request.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ConnectionRejectedReason {
+    NimbleVersionMismatch = 0x01,
+    ApplicationVersionMismatch = 0x02,
+    SessionFull = 0x03,
+    Banned = 0x04,
+    Unauthorized = 0x05,
+    UnknownSession = 0x06,
+    /// The connection was connected to another session. It has left that session,
+    /// and has to connect again to join the requested one.
+    DifferentSession = 0x07,
+}
+
+impl TryFrom<u8> for ConnectionRejectedReason {
+    type Error = io::Error;
+
+    fn try_from(value: u8) -> io::Result<Self> {
+        Ok(match value {
+            0x01 => Self::NimbleVersionMismatch,
+            0x02 => Self::ApplicationVersionMismatch,
+            0x03 => Self::SessionFull,
+            0x04 => Self::Banned,
+            0x05 => Self::Unauthorized,
+            0x06 => Self::UnknownSession,
+            0x07 => Self::DifferentSession,
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown connection rejected reason 0x{value:0X}"),
+            ))?,
+        })
+    }
+}
+
+impl Display for ConnectionRejectedReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NimbleVersionMismatch => write!(f, "nimble version mismatch"),
+            Self::ApplicationVersionMismatch => write!(f, "application version mismatch"),
+            Self::SessionFull => write!(f, "session full"),
+            Self::Banned => write!(f, "banned"),
+            Self::Unauthorized => write!(f, "unauthorized"),
+            Self::UnknownSession => write!(f, "unknown session"),
+            Self::DifferentSession => write!(f, "different session"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConnectionRejected {
+    pub response_to_request: ClientRequestId,
+    pub reason: ConnectionRejectedReason,
+}
+
+impl Display for ConnectionRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "connection rejected {} {}",
+            self.response_to_request, self.reason
+        )
+    }
+}
+
+impl ConnectionRejected {
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        self.response_to_request.serialize(stream)?;
+        stream.write_u8(self.reason as u8)
+    }
+
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn from_stream(stream: &mut impl ReadOctetStream) -> io::Result<Self> {
+        Ok(Self {
+            response_to_request: ClientRequestId::deserialize(stream)?,
+            reason: ConnectionRejectedReason::try_from(stream.read_u8()?)?,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct PongInfo {
     pub lower_millis: u16,
@@ -200,6 +291,9 @@ pub enum HostToClientCommands<StepT: Deserialize + Serialize + Debug + Clone + D
     BlobStreamChannel(SenderToReceiverFrontCommands),
     ConnectType(ConnectionAccepted),
     Pong(PongInfo),
+    LeaveGame(LeaveGameAccepted),
+    ConnectionRejected(ConnectionRejected),
+    JoinGameRejected(JoinGameRejected),
 }
 
 impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Serialize
@@ -216,6 +310,9 @@ impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Serialize
             Self::BlobStreamChannel(blob_stream_command) => blob_stream_command.to_stream(stream),
             Self::ConnectType(connect_response) => connect_response.to_stream(stream),
             Self::Pong(pong_info) => pong_info.serialize(stream),
+            Self::LeaveGame(leave_game_response) => leave_game_response.to_stream(stream),
+            Self::ConnectionRejected(connection_rejected) => connection_rejected.to_stream(stream),
+            Self::JoinGameRejected(join_game_rejected) => join_game_rejected.to_stream(stream),
         }
     }
 }
@@ -241,6 +338,15 @@ impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Display
                 write!(f, "ConnectResponse({connect_response})")
             }
             Self::Pong(pong_info) => write!(f, "Pong({pong_info:?})"),
+            Self::LeaveGame(leave_game_response) => {
+                write!(f, "LeaveGameResponse({leave_game_response})")
+            }
+            Self::ConnectionRejected(connection_rejected) => {
+                write!(f, "ConnectionRejected({connection_rejected})")
+            }
+            Self::JoinGameRejected(join_game_rejected) => {
+                write!(f, "JoinGameRejected({join_game_rejected})")
+            }
         }
     }
 }
@@ -264,6 +370,15 @@ impl<StepT: Clone + Debug + Serialize + Deserialize + Display> Deserialize
                 Self::ConnectType(ConnectionAccepted::from_stream(stream)?)
             }
             HostToClientCommand::Pong => Self::Pong(PongInfo::deserialize(stream)?),
+            HostToClientCommand::LeaveGame => {
+                Self::LeaveGame(LeaveGameAccepted::from_stream(stream)?)
+            }
+            HostToClientCommand::ConnectionRejected => {
+                Self::ConnectionRejected(ConnectionRejected::from_stream(stream)?)
+            }
+            HostToClientCommand::JoinGameRejected => {
+                Self::JoinGameRejected(JoinGameRejected::from_stream(stream)?)
+            }
         })
     }
 }
@@ -283,11 +398,18 @@ impl<StepT: Deserialize + Serialize + Debug + Display + Clone> From<&HostToClien
             }
             HostToClientCommands::ConnectType(_) => HostToClientCommand::Connect as Self,
             HostToClientCommands::Pong(_) => HostToClientCommand::Pong as Self,
+            HostToClientCommands::LeaveGame(_) => HostToClientCommand::LeaveGame as Self,
+            HostToClientCommands::ConnectionRejected(_) => {
+                HostToClientCommand::ConnectionRejected as Self
+            }
+            HostToClientCommands::JoinGameRejected(_) => {
+                HostToClientCommand::JoinGameRejected as Self
+            }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct PartyAndSessionSecret {
     pub session_secret: SessionConnectionSecret,
     pub party_id: u8,
@@ -408,11 +530,135 @@ impl JoinGameAccepted {
     }
 }
 
+/// The reason the host gives for not accepting a join game request.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum JoinGameRejectedReason {
+    NoLocalPlayers = 0x01,
+    TooManyLocalPlayers = 0x02,
+    TooManyParticipantsForConnection = 0x03,
+    SessionFull = 0x04,
+    UnknownSessionSecret = 0x05,
+    Unauthorized = 0x06,
+}
+
+impl TryFrom<u8> for JoinGameRejectedReason {
+    type Error = io::Error;
+
+    fn try_from(value: u8) -> io::Result<Self> {
+        Ok(match value {
+            0x01 => Self::NoLocalPlayers,
+            0x02 => Self::TooManyLocalPlayers,
+            0x03 => Self::TooManyParticipantsForConnection,
+            0x04 => Self::SessionFull,
+            0x05 => Self::UnknownSessionSecret,
+            0x06 => Self::Unauthorized,
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown join game rejected reason 0x{value:0X}"),
+            ))?,
+        })
+    }
+}
+
+impl Display for JoinGameRejectedReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoLocalPlayers => write!(f, "no local players"),
+            Self::TooManyLocalPlayers => write!(f, "too many local players"),
+            Self::TooManyParticipantsForConnection => {
+                write!(f, "too many participants for connection")
+            }
+            Self::SessionFull => write!(f, "session full"),
+            Self::UnknownSessionSecret => write!(f, "unknown session secret"),
+            Self::Unauthorized => write!(f, "unauthorized"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct JoinGameRejected {
+    pub client_request_id: ClientRequestId,
+    pub reason: JoinGameRejectedReason,
+}
+
+impl Display for JoinGameRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "JoinGameRejected {} {}",
+            self.client_request_id, self.reason
+        )
+    }
+}
+
+impl JoinGameRejected {
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        self.client_request_id.serialize(stream)?;
+        stream.write_u8(self.reason as u8)
+    }
+
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn from_stream(stream: &mut impl ReadOctetStream) -> io::Result<Self> {
+        Ok(Self {
+            client_request_id: ClientRequestId::deserialize(stream)?,
+            reason: JoinGameRejectedReason::try_from(stream.read_u8()?)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct LeaveGameAccepted {
+    pub client_request_id: ClientRequestId,
+    pub participants: JoinGameParticipants,
+}
+
+impl Display for LeaveGameAccepted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LeaveGameAccepted {} {:?}",
+            self.client_request_id, self.participants
+        )
+    }
+}
+
+impl LeaveGameAccepted {
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
+        self.client_request_id.serialize(stream)?;
+        self.participants.to_stream(stream)
+    }
+
+    /// # Errors
+    ///
+    /// `io::Error` // TODO:
+    pub fn from_stream(stream: &mut impl ReadOctetStream) -> io::Result<Self> {
+        Ok(Self {
+            client_request_id: ClientRequestId::deserialize(stream)?,
+            participants: JoinGameParticipants::from_stream(stream)?,
+        })
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct GameStepResponseHeader {
     pub connection_buffer_count: u8,
     pub delta_buffer: i8,
     pub next_expected_tick_id: TickId,
+    /// Set while the session is paused. No authoritative steps are produced from this tick
+    /// until the session is resumed, and then the session continues from this same tick.
+    pub session_paused_at: Option<TickId>,
+    /// Set while the session is paused and a resume is scheduled. The number of milliseconds
+    /// until the session continues from `session_paused_at`.
+    pub session_resume_in_millis: Option<u16>,
 }
 
 impl Display for GameStepResponseHeader {
@@ -421,7 +667,14 @@ impl Display for GameStepResponseHeader {
             f,
             "game_step_response: count:{} expected:{} delta-buf:{}",
             self.connection_buffer_count, self.next_expected_tick_id, self.delta_buffer
-        )
+        )?;
+        if let Some(paused_at) = self.session_paused_at {
+            write!(f, " paused-at:{paused_at}")?;
+        }
+        if let Some(resume_in_millis) = self.session_resume_in_millis {
+            write!(f, " resume-in:{resume_in_millis}ms")?;
+        }
+        Ok(())
     }
 }
 
@@ -432,7 +685,16 @@ impl GameStepResponseHeader {
     pub fn to_stream(&self, stream: &mut impl WriteOctetStream) -> io::Result<()> {
         stream.write_u8(self.connection_buffer_count)?;
         stream.write_i8(self.delta_buffer)?;
-        TickIdUtil::to_stream(self.next_expected_tick_id, stream)
+        TickIdUtil::to_stream(self.next_expected_tick_id, stream)?;
+        stream.write_u8(u8::from(self.session_paused_at.is_some()))?;
+        if let Some(paused_at) = self.session_paused_at {
+            TickIdUtil::to_stream(paused_at, stream)?;
+        }
+        stream.write_u8(u8::from(self.session_resume_in_millis.is_some()))?;
+        if let Some(resume_in_millis) = self.session_resume_in_millis {
+            stream.write_u16(resume_in_millis)?;
+        }
+        Ok(())
     }
 
     /// # Errors
@@ -443,6 +705,16 @@ impl GameStepResponseHeader {
             connection_buffer_count: stream.read_u8()?,
             delta_buffer: stream.read_i8()?,
             next_expected_tick_id: TickIdUtil::from_stream(stream)?,
+            session_paused_at: if stream.read_u8()? != 0 {
+                Some(TickIdUtil::from_stream(stream)?)
+            } else {
+                None
+            },
+            session_resume_in_millis: if stream.read_u8()? != 0 {
+                Some(stream.read_u16()?)
+            } else {
+                None
+            },
         })
     }
 }
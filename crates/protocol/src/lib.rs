@@ -23,7 +23,6 @@ impl fmt::Display for ClientRequestId {
 
 impl ClientRequestId {
     #[must_use]
-
     pub const fn new(value: u8) -> Self {
         Self(value)
     }
@@ -47,6 +46,34 @@ impl Deserialize for ClientRequestId {
     }
 }
 
+/// Identifies one of the game sessions on a host.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(pub u16);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SessionId({})", self.0)
+    }
+}
+
+impl Serialize for SessionId {
+    fn serialize(&self, stream: &mut impl WriteOctetStream) -> Result<()>
+    where
+        Self: Sized,
+    {
+        stream.write_u16(self.0)
+    }
+}
+
+impl Deserialize for SessionId {
+    fn deserialize(stream: &mut impl ReadOctetStream) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(stream.read_u16()?))
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Version {
     pub major: u16,
@@ -56,7 +83,6 @@ pub struct Version {
 
 impl Version {
     #[must_use]
-
     pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
         Self {
             major,
@@ -88,7 +114,7 @@ impl Version {
     }
 }
 
-pub const NIMBLE_PROTOCOL_VERSION: Version = Version::new(0, 0, 5);
+pub const NIMBLE_PROTOCOL_VERSION: Version = Version::new(0, 0, 9);
 
 #[derive(PartialEq, Copy, Clone, Eq)]
 pub struct SessionConnectionSecret {
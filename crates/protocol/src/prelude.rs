@@ -3,8 +3,14 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 pub use {
-    crate::client_to_host::{ClientToHostCommands, JoinGameRequest, StepsAck, StepsRequest},
-    crate::host_to_client::{GameStepResponse, HostToClientCommands, JoinGameAccepted},
+    crate::client_to_host::{
+        ClientToHostCommands, JoinGameRequest, LeaveGameRequest, SessionPauseRequest, StepsAck,
+        StepsRequest,
+    },
+    crate::host_to_client::{
+        ConnectionRejected, ConnectionRejectedReason, GameStepResponse, HostToClientCommands,
+        JoinGameAccepted, JoinGameRejected, JoinGameRejectedReason, LeaveGameAccepted,
+    },
     crate::serialize::CombinedSteps,
-    crate::{SessionConnectionSecret, Version},
+    crate::{SessionConnectionSecret, SessionId, Version},
 };
@@ -194,7 +194,7 @@ impl<StepT: Serialize + Deserialize + Debug + Display> InternalAllParticipantVec
                         steps: authoritative_steps_for_one_participant,
                     },
                 )
-                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+                .map_err(io::Error::other)?;
         }
 
         Ok(Self {
@@ -5,15 +5,18 @@
 use flood_rs::prelude::*;
 use nimble_participant::ParticipantId;
 
-use nimble_protocol::host_to_client::{AuthoritativeStepRanges, InternalAuthoritativeStepRanges};
+use nimble_protocol::host_to_client::{
+    AuthoritativeStepRanges, ConnectionRejected, ConnectionRejectedReason, GameStepResponseHeader,
+    InternalAuthoritativeStepRanges, JoinGameRejected, JoinGameRejectedReason,
+};
 
 use nimble_protocol::serialize::{
     CombinedSteps, InternalAllParticipantVectors, InternalAuthoritativeStepRange,
     InternalStepVectorForOneParticipant,
 };
-use nimble_protocol::{ClientRequestId, Version};
+use nimble_protocol::{ClientRequestId, SessionId, Version};
 
-use nimble_protocol::client_to_host::ConnectRequest;
+use nimble_protocol::client_to_host::{ConnectRequest, LeaveGameRequest, SessionPauseRequest};
 use nimble_sample_step::SampleStep;
 use nimble_step_map::StepMap;
 use std::io;
@@ -48,6 +51,8 @@ fn check_connect() {
         use_debug_stream: false,
         application_version: version,
         client_request_id: ClientRequestId(0xff),
+        session_id: SessionId(0x1234),
+        client_token: b"ticket".to_vec(),
     };
     connect.to_stream(&mut out_stream).unwrap();
 
@@ -58,6 +63,97 @@ fn check_connect() {
     assert_eq!(received_connect, connect);
 }
 
+#[test_log::test]
+fn check_leave() {
+    let mut out_stream = OutOctetStream::new();
+    let leave = LeaveGameRequest {
+        client_request_id: ClientRequestId(0x42),
+        local_indices: vec![0, 3],
+    };
+    leave.to_stream(&mut out_stream).unwrap();
+
+    assert_eq!(out_stream.octets_ref(), &[0x42, 0x02, 0x00, 0x03]);
+
+    let mut in_stream = InOctetStream::new(out_stream.octets_ref());
+
+    let received_leave = LeaveGameRequest::from_stream(&mut in_stream).unwrap();
+
+    assert_eq!(received_leave, leave);
+}
+
+#[test_log::test]
+fn check_connection_rejected() {
+    let mut out_stream = OutOctetStream::new();
+    let rejected = ConnectionRejected {
+        response_to_request: ClientRequestId(0x42),
+        reason: ConnectionRejectedReason::SessionFull,
+    };
+    rejected.to_stream(&mut out_stream).unwrap();
+
+    assert_eq!(out_stream.octets_ref(), &[0x42, 0x03]);
+
+    let mut in_stream = InOctetStream::new(out_stream.octets_ref());
+
+    let received_rejected = ConnectionRejected::from_stream(&mut in_stream).unwrap();
+
+    assert_eq!(received_rejected, rejected);
+}
+
+#[test_log::test]
+fn check_join_game_rejected() {
+    let mut out_stream = OutOctetStream::new();
+    let rejected = JoinGameRejected {
+        client_request_id: ClientRequestId(0x13),
+        reason: JoinGameRejectedReason::TooManyLocalPlayers,
+    };
+    rejected.to_stream(&mut out_stream).unwrap();
+
+    assert_eq!(out_stream.octets_ref(), &[0x13, 0x02]);
+
+    let mut in_stream = InOctetStream::new(out_stream.octets_ref());
+
+    let received_rejected = JoinGameRejected::from_stream(&mut in_stream).unwrap();
+
+    assert_eq!(received_rejected, rejected);
+}
+
+#[test_log::test]
+fn check_session_pause() {
+    let mut out_stream = OutOctetStream::new();
+    let pause = SessionPauseRequest { paused: true };
+    pause.to_stream(&mut out_stream).unwrap();
+
+    assert_eq!(out_stream.octets_ref(), &[0x01]);
+
+    let mut in_stream = InOctetStream::new(out_stream.octets_ref());
+
+    let received_pause = SessionPauseRequest::from_stream(&mut in_stream).unwrap();
+
+    assert_eq!(received_pause, pause);
+
+    for (session_paused_at, session_resume_in_millis) in [
+        (None, None),
+        (Some(TickId(0x0102_0304)), None),
+        (Some(TickId(0x0102_0304)), Some(0x0180)),
+    ] {
+        let mut out_stream = OutOctetStream::new();
+        let header = GameStepResponseHeader {
+            connection_buffer_count: 3,
+            delta_buffer: -1,
+            next_expected_tick_id: TickId(0x0102_0304),
+            session_paused_at,
+            session_resume_in_millis,
+        };
+        header.to_stream(&mut out_stream).unwrap();
+
+        let mut in_stream = InOctetStream::new(out_stream.octets_ref());
+
+        let received_header = GameStepResponseHeader::from_stream(&mut in_stream).unwrap();
+
+        assert_eq!(received_header, header);
+    }
+}
+
 #[test_log::test]
 fn check_authoritative() -> io::Result<()> {
     // Prepare all steps
@@ -152,8 +248,7 @@ fn check_authoritative() -> io::Result<()> {
 }
 
 fn create_authoritative_step_range() -> CombinedSteps<SampleStep> {
-    const PARTICIPANT_COUNT: usize = 2;
-    let steps_per_participant = vec![
+    let steps_per_participant = [
         [
             SampleStep::Jump,
             SampleStep::MoveLeft(-10),
@@ -165,8 +260,8 @@ fn create_authoritative_step_range() -> CombinedSteps<SampleStep> {
     let mut authoritative_steps = Vec::new();
     for index in 0..3 {
         let mut authoritative_participants = StepMap::new();
-        for participant_index in 0..PARTICIPANT_COUNT {
-            let sample_step = &steps_per_participant[participant_index][index];
+        for (participant_index, participant_steps) in steps_per_participant.iter().enumerate() {
+            let sample_step = &participant_steps[index];
             authoritative_participants
                 .insert(ParticipantId(participant_index as u8), sample_step.clone())
                 .expect("should be unique participants ids");
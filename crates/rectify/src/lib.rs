@@ -137,6 +137,12 @@ impl<Game: RectifyCallbacks<StepT>, StepT: Clone + Debug + Display> Rectify<Game
         self.assent.next_expected_tick_id()
     }
 
+    /// Discards the authoritative steps and waits for the authoritative step for `tick_id`,
+    /// e.g. the tick of a downloaded game state.
+    pub fn reset_authoritative(&mut self, tick_id: TickId) {
+        self.assent.reset(tick_id);
+    }
+
     ///
     /// # Errors
     ///
@@ -164,7 +170,6 @@ impl<Game: RectifyCallbacks<StepT>, StepT: Clone + Debug + Display> Rectify<Game
     /// # Errors
     ///
     /// `RectifyError` on error // TODO:
-
     #[allow(clippy::missing_panics_doc)]
     pub fn push_authoritative_with_check(
         &mut self,
@@ -201,4 +206,14 @@ impl<Game: RectifyCallbacks<StepT>, StepT: Clone + Debug + Display> Rectify<Game
             self.seer.update(game);
         }
     }
+
+    /// Updates only the authoritative state, without predicting from it. Used when there
+    /// are no local predictions, e.g. when spectating.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - A mutable reference to the game implementing the necessary callback traits.
+    pub fn update_authoritative(&mut self, game: &mut Game) -> UpdateState {
+        self.assent.update(game)
+    }
 }